@@ -0,0 +1,166 @@
+use crate::map::{Legend, Map, Point};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How frames should be shown, see start
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Pace {
+    /// Wait this long after each frame
+    Delay(Duration),
+    /// Wait for Enter after each frame
+    Step,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Settings {
+    pace: Pace,
+    frames: usize,
+}
+
+/// A comfortable pace to watch most simulations
+pub const DELAY: Duration = Duration::from_millis(100);
+
+/// None unless somebody asked to watch, days check this before building frames
+static SETTINGS: Mutex<Option<Settings>> = Mutex::new(None);
+
+/// Show the frames days publish from now on
+pub fn start(pace: Pace) {
+    *SETTINGS.lock().unwrap() = Some(Settings { pace, frames: 0 });
+}
+
+/// Stop showing frames, returning how many were shown
+pub fn stop() -> usize {
+    SETTINGS
+        .lock()
+        .unwrap()
+        .take()
+        .map_or(0, |settings| settings.frames)
+}
+
+/// Whether frames are being shown, so that it's worth the trouble to make them
+/// Check once before a simulation rather than every step, since each frame takes a lock
+pub fn enabled() -> bool {
+    SETTINGS.lock().unwrap().is_some()
+}
+
+const HOME: &str = "\x1b[H\x1b[2J";
+const CURSOR: &str = "\x1b[7m";
+const PATH: &str = "\x1b[1;33m";
+const PLAIN: &str = "\x1b[0m";
+
+/// The Map as text, with the cursor shown inverted and each cell on the path highlighted
+pub fn render<T: Legend + Default>(map: &Map<T>, cursor: Option<Point>, path: &[Point]) -> String {
+    let path: HashSet<Point> = path.iter().copied().collect();
+    let mut s = String::new();
+    for (p, cell) in map.cells() {
+        if p.x == *map.x().start() && p.y != *map.y().start() {
+            s.push('\n');
+        }
+        let style = if Some(p) == cursor {
+            Some(CURSOR)
+        } else if path.contains(&p) {
+            Some(PATH)
+        } else {
+            None
+        };
+        match style {
+            Some(style) => {
+                s.push_str(style);
+                s.push(cell.to_char());
+                s.push_str(PLAIN);
+            }
+            None => s.push(cell.to_char()),
+        }
+    }
+    s.push('\n');
+    s
+}
+
+/// Show the next frame, if frames are being shown
+pub fn show<T: Legend + Default>(map: &Map<T>) {
+    highlight(map, None, &[]);
+}
+
+/// Show the next frame with a cursor and path highlighted, if frames are being shown
+pub fn highlight<T: Legend + Default>(map: &Map<T>, cursor: Option<Point>, path: &[Point]) {
+    let Some(settings) = SETTINGS.lock().unwrap().as_mut().map(|settings| {
+        settings.frames += 1;
+        *settings
+    }) else {
+        return;
+    };
+
+    let mut out = io::stdout().lock();
+    let frame = render(map, cursor, path);
+    let _ = write!(out, "{HOME}{frame}Frame {}", settings.frames);
+    match settings.pace {
+        Pace::Delay(delay) => {
+            let _ = writeln!(out);
+            let _ = out.flush();
+            std::thread::sleep(delay);
+        }
+        Pace::Step => {
+            let _ = write!(
+                out,
+                " - Enter for the next, c to carry on, q to stop watching "
+            );
+            let _ = out.flush();
+            let mut line = String::new();
+            let _ = io::stdin().lock().read_line(&mut line);
+            match line.trim() {
+                "c" => {
+                    let pace = Pace::Delay(DELAY);
+                    *SETTINGS.lock().unwrap() = Some(Settings { pace, ..settings });
+                }
+                "q" => {
+                    stop();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+    struct Cell(bool);
+
+    impl Legend for Cell {
+        fn from_char(ch: char) -> Self {
+            Cell(ch == '#')
+        }
+
+        fn to_char(self) -> char {
+            if self.0 {
+                '#'
+            } else {
+                '.'
+            }
+        }
+    }
+
+    #[test]
+    fn render_overlay() {
+        let map: Map<Cell> = "#..\n.#.\n".parse().unwrap();
+        assert_eq!(render(&map, None, &[]), "#..\n.#.\n");
+        let path = [Point::new(1, 0), Point::new(2, 0)];
+        assert_eq!(
+            render(&map, Some(Point::new(1, 1)), &path),
+            "#\x1b[1;33m.\x1b[0m\x1b[1;33m.\x1b[0m\n.\x1b[7m#\x1b[0m.\n"
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        // Tests run in parallel, but none of the others start an animation
+        assert!(!enabled());
+        let map: Map<Cell> = "#".parse().unwrap();
+        show(&map);
+        assert_eq!(stop(), 0);
+    }
+}
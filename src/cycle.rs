@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A sequence of states, each made by a step from the one before, which eventually repeats
+/// Step number start is the first to be repeated, then every period steps after that
+/// All the finders here go round forever if the sequence never repeats
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// The earliest step with the same state as step n
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.period
+        }
+    }
+
+    /// The state after n steps from initial, without taking more than start + period steps
+    pub fn jump<S, F>(&self, initial: &S, step: F, n: usize) -> S
+    where
+        S: Clone,
+        F: Fn(&S) -> S,
+    {
+        let mut state = initial.clone();
+        for _ in 0..self.equivalent(n) {
+            state = step(&state);
+        }
+        state
+    }
+}
+
+/// Brent's algorithm, which only keeps two states at once and usually takes fewer steps than
+/// Floyd's algorithm
+pub fn brent<S, F>(initial: &S, step: F) -> Cycle
+where
+    S: Clone + Eq,
+    F: Fn(&S) -> S,
+{
+    // Find the period, by having the hare wait for the tortoise at each power of two
+    let (mut power, mut period) = (1, 1);
+    let mut tortoise = initial.clone();
+    let mut hare = step(initial);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // Then with the hare a whole period ahead, they meet at the start
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Floyd's tortoise and hare algorithm, which only keeps two states at once
+pub fn floyd<S, F>(initial: &S, step: F) -> Cycle
+where
+    S: Clone + Eq,
+    F: Fn(&S) -> S,
+{
+    // The hare goes twice as fast, so they meet somewhere in the cycle
+    let mut tortoise = step(initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    // The tortoise starts again and they meet at the start
+    let mut tortoise = initial.clone();
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut period = 1;
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Remember every state, so that the first repeat is noticed straight away, and return them all
+/// The state after n steps is then states[cycle.equivalent(n)] for any n
+pub fn hashed<S, F>(initial: &S, step: F) -> (Cycle, Vec<S>)
+where
+    S: Clone + Eq + Hash,
+    F: Fn(&S) -> S,
+{
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut states = Vec::new();
+    let mut state = initial.clone();
+    loop {
+        if let Some(&start) = seen.get(&state) {
+            let period = states.len() - start;
+            return (Cycle { start, period }, states);
+        }
+        seen.insert(state.clone(), states.len());
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0, 1, 2, 3, 4, 5, 6, 3, 4, 5, 6, 3 ...
+    fn lasso(&n: &u32) -> u32 {
+        if n == 6 {
+            3
+        } else {
+            n + 1
+        }
+    }
+
+    #[test]
+    fn finders_agree() {
+        let expected = Cycle {
+            start: 3,
+            period: 4,
+        };
+        assert_eq!(brent(&0, lasso), expected);
+        assert_eq!(floyd(&0, lasso), expected);
+        let (cycle, states) = hashed(&0, lasso);
+        assert_eq!(cycle, expected);
+        assert_eq!(states, [0, 1, 2, 3, 4, 5, 6]);
+
+        let ring = |&n: &u8| (n + 1) % 5;
+        let expected = Cycle {
+            start: 0,
+            period: 5,
+        };
+        assert_eq!(brent(&2, ring), expected);
+        assert_eq!(floyd(&2, ring), expected);
+        assert_eq!(hashed(&2, ring).0, expected);
+
+        let stuck = |&n: &u8| n.saturating_sub(1);
+        let expected = Cycle {
+            start: 4,
+            period: 1,
+        };
+        assert_eq!(brent(&4, stuck), expected);
+        assert_eq!(floyd(&4, stuck), expected);
+        assert_eq!(hashed(&4, stuck).0, expected);
+    }
+
+    #[test]
+    fn jump() {
+        let cycle = brent(&0, lasso);
+        assert_eq!(cycle.equivalent(2), 2);
+        assert_eq!(cycle.equivalent(10), 6);
+        assert_eq!(cycle.jump(&0, lasso, 10), 6);
+        assert_eq!(cycle.jump(&0, lasso, 1_000_000_000_000), 4);
+        let (cycle, states) = hashed(&0, lasso);
+        assert_eq!(states[cycle.equivalent(1_000_000_000_001)], 5);
+    }
+}
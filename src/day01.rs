@@ -1,7 +1,8 @@
-use history::readfile;
+use history::{readfile, Answer, Contents, InputError, Mistake};
 
 type Number = u32;
 
+#[allow(clippy::manual_abs_diff)]
 fn diff(nums: (Number, Number)) -> Number {
     if nums.0 > nums.1 {
         nums.0 - nums.1
@@ -10,21 +11,37 @@ fn diff(nums: (Number, Number)) -> Number {
     }
 }
 
-pub fn a(filename: &str) {
+fn pair(ctxt: &Contents, line: &str) -> Result<(Number, Number), InputError> {
+    let mut numbers = line.split_ascii_whitespace();
+    let Some(l) = numbers.next() else {
+        return Err(ctxt.error(Mistake::new(
+            line,
+            "There should be a left number on each line",
+        )));
+    };
+    let Some(r) = numbers.next() else {
+        return Err(ctxt.error(Mistake::new(
+            line,
+            "There should also be a right number on each line",
+        )));
+    };
+    if let Some(extra) = numbers.next() {
+        return Err(ctxt.error(Mistake::new(
+            extra,
+            "There should only be two numbers on each line",
+        )));
+    }
+    let l: Number = ctxt.parse(l, "LHS should be a number")?;
+    let r: Number = ctxt.parse(r, "RHS should be a number")?;
+    Ok((l, r))
+}
+
+pub fn a(filename: &str) -> Result<Answer, InputError> {
     let mut left = Vec::new();
     let mut right = Vec::new();
-    let ctxt = readfile(filename);
+    let ctxt = readfile(filename)?;
     for line in ctxt.lines() {
-        let mut numbers = line.split_ascii_whitespace();
-        let l = numbers
-            .next()
-            .expect("There should be a left number on each line");
-        let r = numbers
-            .next()
-            .expect("There should also be a right number on each line");
-        assert_eq!(numbers.next(), None);
-        let l: Number = l.parse().expect("LHS should be a number");
-        let r: Number = r.parse().expect("RHS should be a number");
+        let (l, r) = pair(&ctxt, line)?;
         left.push(l);
         right.push(r);
     }
@@ -33,26 +50,17 @@ pub fn a(filename: &str) {
 
     let pairs = left.into_iter().zip(right);
     let total: Number = pairs.map(diff).sum();
-    println!("Total distance between lists is {total}");
+    Ok(total.into())
 }
 
 use std::collections::HashMap;
 
-pub fn b(filename: &str) {
+pub fn b(filename: &str) -> Result<Answer, InputError> {
     let mut left = Vec::new();
     let mut right: HashMap<Number, usize> = HashMap::new();
-    let ctxt = readfile(filename);
+    let ctxt = readfile(filename)?;
     for line in ctxt.lines() {
-        let mut numbers = line.split_ascii_whitespace();
-        let l = numbers
-            .next()
-            .expect("There should be a left number on each line");
-        let r = numbers
-            .next()
-            .expect("There should also be a right number on each line");
-        assert_eq!(numbers.next(), None);
-        let l: Number = l.parse().expect("LHS should be a number");
-        let r: Number = r.parse().expect("RHS should be a number");
+        let (l, r) = pair(&ctxt, line)?;
         left.push(l);
         *right.entry(r).or_default() += 1;
     }
@@ -64,5 +72,20 @@ pub fn b(filename: &str) {
         }
     }
 
-    println!("Similarity score is {score}");
+    Ok(score.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/01example"), Ok(Answer::Number(11)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/01example"), Ok(Answer::Number(31)));
+    }
 }
@@ -1,12 +1,15 @@
-use history::readfile;
+use history::{readfile, Answer, Contents, InputError, Mistake};
 
 type Number = i32;
 
+// A single level, as left when dampen removes one of two, is safe
 fn is_safe(mut n: impl Iterator<Item = Number>) -> bool {
-    let mut a = n.next().expect("Each line should have at least one number");
-    let mut b = n
-        .next()
-        .expect("Each line should have at least two numbers");
+    let Some(mut a) = n.next() else {
+        return true;
+    };
+    let Some(mut b) = n.next() else {
+        return true;
+    };
     if a > b {
         // Descending
         loop {
@@ -39,23 +42,30 @@ fn is_safe(mut n: impl Iterator<Item = Number>) -> bool {
     true
 }
 
-fn basic(s: &str) -> bool {
-    is_safe(
-        s.split_ascii_whitespace()
-            .map(|s| s.parse::<Number>().unwrap()),
-    )
+fn report(ctxt: &Contents, line: &str) -> Result<Vec<Number>, InputError> {
+    let levels = line
+        .split_ascii_whitespace()
+        .map(|s| ctxt.parse(s, "Each level should be a number"))
+        .collect::<Result<Vec<Number>, InputError>>()?;
+    if levels.len() < 2 {
+        return Err(ctxt.error(Mistake::new(
+            line,
+            "Each report should have at least two levels",
+        )));
+    }
+    Ok(levels)
+}
+
+fn basic(all: &[Number]) -> bool {
+    is_safe(all.iter().copied())
 }
 
-fn dampen(s: &str) -> bool {
-    let n = s
-        .split_ascii_whitespace()
-        .map(|s| s.parse::<Number>().unwrap());
-    let all: Vec<Number> = n.collect();
+fn dampen(all: &[Number]) -> bool {
     if is_safe(all.iter().copied()) {
         return true;
     }
     for skip in 0..all.len() {
-        let mut skipped = all.clone();
+        let mut skipped = all.to_vec();
         skipped.remove(skip);
         if is_safe(skipped.into_iter()) {
             return true;
@@ -64,24 +74,50 @@ fn dampen(s: &str) -> bool {
     false
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut count = 0;
     for line in ctxt.lines() {
-        if basic(line) {
+        if basic(&report(&ctxt, line)?) {
             count += 1;
         }
     }
-    println!("{count} reports are safe");
+    Ok(count.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut count = 0;
     for line in ctxt.lines() {
-        if dampen(line) {
+        if dampen(&report(&ctxt, line)?) {
             count += 1;
         }
     }
-    println!("{count} reports are now safe");
+    Ok(count.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/02example"), Ok(Answer::Number(2)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/02example"), Ok(Answer::Number(4)));
+    }
+
+    #[test]
+    fn two_levels() {
+        let ctxt = Contents {
+            filename: "reports".to_owned(),
+            text: "5 5\n".to_owned(),
+        };
+        let levels = report(&ctxt, "5 5").unwrap();
+        assert!(!basic(&levels));
+        assert!(dampen(&levels));
+    }
 }
@@ -1,10 +1,10 @@
-use history::readfile;
+use history::{readfile, Answer, InputError};
 use regex::Regex;
 
 type Num = u64;
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let re = Regex::new(r"mul\(([0-9]{1,3})\,([0-9]{1,3})\)").expect("This regex should compile");
 
     let mut total = 0;
@@ -23,11 +23,11 @@ pub fn a(filename: &str) {
             total += left * right;
         }
     }
-    println!("Adding up all the uncorrupted multiplications gives: {total}");
+    Ok(total.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let re = Regex::new(r"do\(\)|don\'t\(\)|mul\(([0-9]{1,3})\,([0-9]{1,3})\)")
         .expect("This regex should compile");
 
@@ -59,5 +59,20 @@ pub fn b(filename: &str) {
             }
         }
     }
-    println!("Adding up only enabled multiplications gives: {total}");
+    Ok(total.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/03example"), Ok(Answer::Number(161)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/03example"), Ok(Answer::Number(48)));
+    }
 }
@@ -1,5 +1,5 @@
-use history::map::Map;
-use history::readfile;
+use history::map::{Map, SURROUNDING};
+use history::{readfile, Answer, InputError};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Letter {
@@ -51,16 +51,7 @@ fn check(words: &Search, x: isize, y: isize) -> usize {
     }
 
     let mut count = 0;
-    for (dx, dy) in [
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ] {
+    for (dx, dy) in SURROUNDING {
         if check_dir(words, x, y, dx, dy) {
             count += 1;
         }
@@ -90,28 +81,34 @@ fn xmas(words: &Search, x: isize, y: isize) -> bool {
     )
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let words: Search = ctxt.value().parse().expect("should be a word search");
-    let mut total = 0;
-    for y in words.y() {
-        for x in words.x() {
-            total += check(&words, x, y);
-        }
-    }
-    println!("{total} XMAS found");
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let words: Search = ctxt.map(ctxt.value())?;
+    let total: usize = words.cells().map(|(p, _)| check(&words, p.x, p.y)).sum();
+    Ok(total.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let words: Search = ctxt.value().parse().expect("should be a word search");
-    let mut count = 0;
-    for y in words.y() {
-        for x in words.x() {
-            if xmas(&words, x, y) {
-                count += 1;
-            }
-        }
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let words: Search = ctxt.map(ctxt.value())?;
+    let count = words
+        .cells()
+        .filter(|&(p, _)| xmas(&words, p.x, p.y))
+        .count();
+    Ok(count.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/04example"), Ok(Answer::Number(18)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/04example"), Ok(Answer::Number(9)));
     }
-    println!("{count} X-MAS found");
 }
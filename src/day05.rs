@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{parse, readfile, Answer, Contents, InputError, Mistake};
 
 type Num = i32;
 
@@ -8,21 +8,16 @@ struct Rule {
     after: Num,
 }
 
-use std::str::FromStr;
-impl FromStr for Rule {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Rule {
+    fn parse(s: &str) -> Result<Self, Mistake<'_>> {
         let Some((before, after)) = s.split_once('|') else {
-            return Err("Missing pipe");
+            return Err(Mistake::new(s, "Missing pipe"));
         };
-        let before: Num = before.parse().map_err(|_| "Should be a number")?;
-        let after: Num = after.parse().map_err(|_| "Both should be numbers")?;
+        let before: Num = parse(before, "Should be a number")?;
+        let after: Num = parse(after, "Both should be numbers")?;
         Ok(Rule { before, after })
     }
-}
 
-impl Rule {
     fn obey(&self, nums: &[Num]) -> bool {
         let mut late = false;
         for n in nums {
@@ -56,20 +51,26 @@ impl Rule {
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+fn pages(ctxt: &Contents, line: &str) -> Result<Vec<Num>, InputError> {
+    line.split(',')
+        .map(|s| ctxt.parse(s, "Page numbers should be numbers"))
+        .collect()
+}
+
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut rules = Vec::new();
     let mut lines = ctxt.lines();
     for line in lines.by_ref() {
         if line.is_empty() {
             break;
         }
-        let rule: Rule = line.parse().expect("should be a page rule");
+        let rule = ctxt.locate(Rule::parse(line))?;
         rules.push(rule);
     }
     let mut sum = 0;
     'outer: for line in lines {
-        let nums: Vec<Num> = line.split(',').map(|s| s.parse().unwrap()).collect();
+        let nums = pages(&ctxt, line)?;
         for rule in rules.iter() {
             if !rule.obey(&nums) {
                 continue 'outer;
@@ -78,23 +79,23 @@ pub fn a(filename: &str) {
         let middle = nums.len() / 2;
         sum += nums[middle];
     }
-    println!("Middle page numbers of correct updates sum to {sum}");
+    Ok(sum.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut rules = Vec::new();
     let mut lines = ctxt.lines();
     for line in lines.by_ref() {
         if line.is_empty() {
             break;
         }
-        let rule: Rule = line.parse().expect("should be a page rule");
+        let rule = ctxt.locate(Rule::parse(line))?;
         rules.push(rule);
     }
     let mut sum = 0;
     for line in lines {
-        let mut nums: Vec<Num> = line.split(',').map(|s| s.parse().unwrap()).collect();
+        let mut nums = pages(&ctxt, line)?;
         let mut corrected = false;
         loop {
             let mut this = false;
@@ -114,5 +115,20 @@ pub fn b(filename: &str) {
             sum += nums[middle];
         }
     }
-    println!("Middle page numbers of corrected updates sum to {sum}");
+    Ok(sum.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/05example"), Ok(Answer::Number(143)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/05example"), Ok(Answer::Number(123)));
+    }
 }
@@ -1,5 +1,7 @@
-use history::map::Map;
-use history::readfile;
+use history::animate;
+use history::cycle;
+use history::map::{Direction, Map, Point};
+use history::{readfile, Answer, Contents, InputError, Mistake};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Legend {
@@ -8,16 +10,20 @@ enum Legend {
     Empty,
     Guard,
     Obstacle,
-    Path(u8),
+    Path,
 }
 
 impl history::map::Legend for Legend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '.' => Self::Empty,
-            '^' => Self::Guard,
-            '#' => Self::Obstacle,
-            _ => panic!("Unexpected symbol on map"),
+            '.' => Some(Self::Empty),
+            '^' => Some(Self::Guard),
+            '#' => Some(Self::Obstacle),
+            _ => None,
         }
     }
 
@@ -26,6 +32,7 @@ impl history::map::Legend for Legend {
             Self::Empty => '.',
             Self::Guard => '^',
             Self::Obstacle => '#',
+            Self::Path => 'X',
             _ => panic!("Unexpected symbol on map"),
         }
     }
@@ -33,125 +40,129 @@ impl history::map::Legend for Legend {
 
 type Lab = Map<Legend>;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct Guard {
+    pos: Point,
+    head: Direction,
 }
 
-impl Direction {
-    const fn turn(self) -> Self {
-        use Direction::*;
-
-        match self {
-            North => East,
-            East => South,
-            South => West,
-            West => North,
+impl Guard {
+    /// The only guard in the lab, which was parsed from the text of ctxt
+    fn find(ctxt: &Contents, lab: &Lab) -> Result<Self, InputError> {
+        let guards = lab.find_points(|l| l == Legend::Guard);
+        match guards[..] {
+            [] => Err(ctxt.missing("There should be a guard in the lab")),
+            [pos] => Ok(Guard {
+                pos,
+                head: Direction::North,
+            }),
+            [_, other, ..] => {
+                let line = ctxt.value().lines().nth(other.y as usize).unwrap_or("");
+                let x = other.x as usize;
+                let guard = line.get(x..x + 1).unwrap_or(line);
+                Err(ctxt.error(Mistake::new(
+                    guard,
+                    "There should only be one guard in the lab",
+                )))
+            }
         }
     }
+}
 
-    const fn bits(&self) -> u8 {
-        use Direction::*;
+fn inside(lab: &Lab, p: Point) -> bool {
+    !matches!(lab.read_at(p), None | Some(Legend::Edge))
+}
 
-        match self {
-            North => 1,
-            East => 2,
-            South => 4,
-            West => 8,
-        }
+/// Where the guard is after their next move, perhaps with an extra obstacle in the lab
+/// Once the guard has left the lab they stay gone
+fn step(lab: &Lab, obstacle: Option<Point>, guard: &Guard) -> Guard {
+    if !inside(lab, guard.pos) {
+        return *guard;
     }
-
-    const fn go(&self) -> (isize, isize) {
-        use Direction::*;
-
-        match self {
-            North => (0, -1),
-            East => (1, 0),
-            South => (0, 1),
-            West => (-1, 0),
+    let ahead = guard.pos + guard.head;
+    if Some(ahead) == obstacle || lab.read_at(ahead) == Some(Legend::Obstacle) {
+        Guard {
+            head: guard.head.clockwise(),
+            ..*guard
+        }
+    } else {
+        Guard {
+            pos: ahead,
+            ..*guard
         }
     }
 }
 
-fn loops(lab: &mut Lab) -> bool {
-    use Direction::*;
-    let mut d = North;
-
-    let guards = lab.find(|l| l == Legend::Guard);
-    assert_eq!(guards.len(), 1);
-    let (mut x, mut y) = guards[0];
-    // Now that we know where the guard was, remove from map
-    lab.write(x, y, Legend::Empty);
-
-    loop {
-        let mv = d.go();
-        match lab.read(x + mv.0, y + mv.1) {
-            None | Some(Legend::Edge) => {
-                lab.write(x, y, Legend::Path(0));
-                return false;
-            }
-            Some(Legend::Obstacle) => {
-                d = d.turn();
-            }
-            Some(Legend::Empty) | Some(Legend::Path(_)) => {
-                match lab.read(x, y) {
-                    Some(Legend::Empty) => {
-                        lab.write(x, y, Legend::Path(d.bits()));
-                    }
-                    Some(Legend::Path(track)) => {
-                        if track & d.bits() != 0 {
-                            return true;
-                        }
-                        lab.write(x, y, Legend::Path(track | d.bits()));
-                    }
-                    _ => panic!("Should never happen, guard was stood somewhere impossible"),
-                }
-                x += mv.0;
-                y += mv.1;
-            }
-            Some(item) => panic!("Unexpected item on map {item:?}"),
+/// Whether the guard goes round in circles forever rather than leaving the lab
+/// Having left, each step is the same as the last, but a loop inside takes at least four steps
+fn loops(lab: &Lab, obstacle: Option<Point>, guard: Guard) -> bool {
+    cycle::brent(&guard, |g| step(lab, obstacle, g)).period > 1
+}
+
+/// Mark each position the guard walks through until they leave the lab
+fn walk(lab: &mut Lab, mut guard: Guard) {
+    let watch = animate::enabled();
+    while inside(lab, guard.pos) {
+        lab.write_at(guard.pos, Legend::Path);
+        guard = step(lab, None, &guard);
+        if watch {
+            animate::highlight(lab, Some(guard.pos), &[]);
         }
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut lab: Lab = ctxt
-        .value()
-        .parse()
-        .expect("should be a map of the suit lab");
-    assert!(!loops(&mut lab)); // The guard does not loop
-    let count = lab.count(|&&l| matches!(l, Legend::Path(_)));
-    println!("Guard visits {count} distinct locations before leaving");
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut lab: Lab = ctxt.map(ctxt.value())?;
+    let guard = Guard::find(&ctxt, &lab)?;
+    assert!(!loops(&lab, None, guard)); // The guard does not loop
+    walk(&mut lab, guard);
+    let count = lab.count(|&&l| l == Legend::Path);
+    Ok(count.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let lab: Lab = ctxt
-        .value()
-        .parse()
-        .expect("should be a map of the suit lab");
-    let guards = lab.find(|l| l == Legend::Guard);
-    assert_eq!(guards.len(), 1);
-    let (gx, gy) = guards[0];
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let lab: Lab = ctxt.map(ctxt.value())?;
+    let guard = Guard::find(&ctxt, &lab)?;
+    assert!(!loops(&lab, None, guard)); // The guard does not loop
     let mut preview = lab.clone();
-    assert!(!loops(&mut preview)); // The guard does not loop
+    walk(&mut preview, guard);
 
     // No point placing an obstacle where the guard never goes
-    let possible = preview.find(|l| matches!(l, Legend::Path(_)));
-
-    let mut places = 0;
-    for (x, y) in possible {
-        if x != gx || y != gy {
-            let mut attempt = lab.clone();
-            attempt.write(x, y, Legend::Obstacle);
-            if loops(&mut attempt) {
-                places += 1;
-            }
-        }
+    let possible = preview.find_points(|l| l == Legend::Path);
+
+    let places = possible
+        .into_iter()
+        .filter(|&p| p != guard.pos && loops(&lab, Some(p), guard))
+        .count();
+    Ok(places.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/06example"), Ok(Answer::Number(41)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/06example"), Ok(Answer::Number(6)));
+    }
+
+    #[test]
+    fn guards() {
+        let ctxt = Contents {
+            filename: "lab".to_owned(),
+            text: "..#\n^.^\n".to_owned(),
+        };
+        let lab: Lab = ctxt.map(ctxt.value()).unwrap();
+        let e = Guard::find(&ctxt, &lab).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "^"));
+        let lab: Lab = ctxt.map("..#").unwrap();
+        assert_eq!(Guard::find(&ctxt, &lab).unwrap_err().line, 3);
     }
-    println!("Obstruction could go in {places} different places");
 }
@@ -1,22 +1,26 @@
-use history::readfile;
+use history::{parse, readfile, Answer, InputError, Mistake};
 
 type Num = u64;
 
-fn handle(line: &str) -> (Num, Num, Vec<Num>) {
+fn handle(line: &str) -> Result<(Num, Num, Vec<Num>), Mistake<'_>> {
     let (goal, rest) = line
         .split_once(": ")
-        .expect("lines should have : separator");
-    let goal: Num = goal.parse().expect("goal should be a number");
-    let (first, rest) = rest
-        .split_once(' ')
-        .expect("lines should then have at least two numbers");
-    let first: Num = first.parse().expect("should all be numbers");
-    let nums: Vec<Num> = rest.split(' ').map(|s| s.parse().unwrap()).collect();
-    (goal, first, nums)
+        .ok_or(Mistake::new(line, "lines should have : separator"))?;
+    let goal: Num = parse(goal, "goal should be a number")?;
+    let (first, rest) = rest.split_once(' ').ok_or(Mistake::new(
+        rest,
+        "lines should then have at least two numbers",
+    ))?;
+    let first: Num = parse(first, "should all be numbers")?;
+    let nums = rest
+        .split(' ')
+        .map(|s| parse(s, "should all be numbers"))
+        .collect::<Result<Vec<Num>, _>>()?;
+    Ok((goal, first, nums))
 }
 
-fn check(line: &str) -> Num {
-    let (goal, first, mut nums) = handle(line);
+fn check(line: &str) -> Result<Num, Mistake<'_>> {
+    let (goal, first, mut nums) = handle(line)?;
     // Use nums as a stack
     nums.reverse();
     let mut sums: Vec<Num> = Vec::new();
@@ -34,19 +38,19 @@ fn check(line: &str) -> Num {
         sums = next;
     }
     if sums.contains(&goal) {
-        goal
+        Ok(goal)
     } else {
-        0
+        Ok(0)
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut total = 0;
     for line in ctxt.lines() {
-        total += check(line);
+        total += ctxt.locate(check(line))?;
     }
-    println!("Calibration result is {total}");
+    Ok(total.into())
 }
 
 fn last_digits(n: Num, d: Num) -> Option<Num> {
@@ -61,8 +65,8 @@ fn last_digits(n: Num, d: Num) -> Option<Num> {
     }
 }
 
-fn three(line: &str) -> Num {
-    let (goal, first, mut nums) = handle(line);
+fn three(line: &str) -> Result<Num, Mistake<'_>> {
+    let (goal, first, mut nums) = handle(line)?;
     let mut sums: Vec<Num> = Vec::new();
     sums.push(goal);
     while let Some(d) = nums.pop() {
@@ -81,17 +85,32 @@ fn three(line: &str) -> Num {
         sums = next;
     }
     if sums.contains(&first) {
-        goal
+        Ok(goal)
     } else {
-        0
+        Ok(0)
     }
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut total = 0;
     for line in ctxt.lines() {
-        total += three(line);
+        total += ctxt.locate(three(line))?;
+    }
+    Ok(total.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/07example"), Ok(Answer::Number(3749)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/07example"), Ok(Answer::Number(11387)));
     }
-    println!("Revised calibration result is {total}");
 }
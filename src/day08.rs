@@ -1,5 +1,5 @@
-use history::map::Map;
-use history::readfile;
+use history::map::{BitMap, Map};
+use history::{readfile, Answer, InputError};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Legend {
@@ -11,10 +11,14 @@ enum Legend {
 
 impl history::map::Legend for Legend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '.' => Self::Empty,
-            '0'..='9' | 'a'..='z' | 'A'..='Z' => Self::Antenna(ch),
-            _ => panic!("Unexpected symbol on map"),
+            '.' => Some(Self::Empty),
+            '0'..='9' | 'a'..='z' | 'A'..='Z' => Some(Self::Antenna(ch)),
+            _ => None,
         }
     }
 
@@ -28,7 +32,7 @@ impl history::map::Legend for Legend {
 }
 
 type Bunny = Map<Legend>;
-type Anti = Map<bool>;
+type Anti = BitMap;
 
 fn antinode(map: &Bunny, anti: &mut Anti, x: isize, y: isize) {
     match map.read(x, y).unwrap_or_default() {
@@ -50,22 +54,19 @@ fn check(map: &Bunny, anti: &mut Anti, ch: char) {
 }
 
 fn find_anti_nodes(map: &Bunny) -> Anti {
-    let mut anti: Anti = Map::ranged(map.x(), map.y());
+    let mut anti = Anti::ranged(map.x(), map.y());
     for symbol in ('0'..='9').chain('a'..='z').chain('A'..='Z') {
         check(map, &mut anti, symbol);
     }
     anti
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Bunny = ctxt
-        .value()
-        .parse()
-        .expect("should be a map of the antennas");
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Bunny = ctxt.map(ctxt.value())?;
     let anti = find_anti_nodes(&map);
-    let count = anti.count(|&&n| n);
-    println!("{count} unique locations contain an antinode within the map");
+    let count = anti.count();
+    Ok(count.into())
 }
 
 fn resonant(map: &Bunny, anti: &mut Anti, diameter: isize, ch: char) {
@@ -85,19 +86,31 @@ fn consider_resonance(map: &Bunny) -> Anti {
     let width = 1 + bx - ax;
     let height = 1 + by - ay;
     let diameter = core::cmp::max(width, height);
-    let mut anti: Anti = Map::ranged(map.x(), map.y());
+    let mut anti = Anti::ranged(map.x(), map.y());
     for symbol in ('0'..='9').chain('a'..='z').chain('A'..='Z') {
         resonant(map, &mut anti, diameter, symbol);
     }
     anti
 }
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Bunny = ctxt
-        .value()
-        .parse()
-        .expect("should be a map of the antennas");
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Bunny = ctxt.map(ctxt.value())?;
     let anti = consider_resonance(&map);
-    let count = anti.count(|&&n| n);
-    println!("With resonance, {count} unique locations contain an antinode within the map");
+    let count = anti.count();
+    Ok(count.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/08example"), Ok(Answer::Number(14)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/08example"), Ok(Answer::Number(34)));
+    }
 }
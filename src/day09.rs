@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{readfile, Answer, InputError, Mistake};
 
 type Num = u32;
 
@@ -43,15 +43,19 @@ struct Diskmap {
     v: Vec<Block>,
 }
 
-use std::str::FromStr;
-impl FromStr for Diskmap {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Diskmap {
+    fn parse(s: &str) -> Result<Self, Mistake<'_>> {
         let mut v: Vec<Block> = Vec::new();
         let mut id: Num = 0;
         let mut free = false;
-        for blocks in s.trim().chars().map(|c| c.to_digit(10).unwrap()) {
+        let s = s.trim();
+        for (n, c) in s.char_indices() {
+            let Some(blocks) = c.to_digit(10) else {
+                return Err(Mistake::new(
+                    &s[n..(n + c.len_utf8())],
+                    "Diskmaps should only have digits",
+                ));
+            };
             if free {
                 for _ in 0..blocks {
                     v.push(Block::free());
@@ -213,16 +217,31 @@ impl Diskmap {
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut disk: Diskmap = ctxt.value().parse().expect("input should be a diskmap");
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut disk = ctxt.locate(Diskmap::parse(ctxt.value()))?;
     disk.crush();
-    println!("Checksum is {}", disk.checksum());
+    Ok(disk.checksum().into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut disk: Diskmap = ctxt.value().parse().expect("input should be a diskmap");
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut disk = ctxt.locate(Diskmap::parse(ctxt.value()))?;
     disk.defrag();
-    println!("Defragged checksum is {}", disk.checksum());
+    Ok(disk.checksum().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/09example"), Ok(Answer::Number(1928)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/09example"), Ok(Answer::Number(2858)));
+    }
 }
@@ -1,15 +1,21 @@
 use history::map::Map;
-use history::readfile;
+use history::{readfile, Answer, InputError};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 struct Elevation(Option<u8>);
 
 impl history::map::Legend for Elevation {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '.' => Elevation(None),
-            '0'..='9' => Elevation(Some(ch.to_digit(10).unwrap().try_into().unwrap())),
-            _ => panic!("Unexpected symbol on map"),
+            '.' => Some(Elevation(None)),
+            '0'..='9' => Some(Elevation(Some(
+                ch.to_digit(10).unwrap().try_into().unwrap(),
+            ))),
+            _ => None,
         }
     }
 
@@ -29,9 +35,9 @@ fn score(map: &Trails, x: isize, y: isize, unique: bool) -> u32 {
     for digit in 1..=9 {
         let mut next = Vec::with_capacity(camp.len());
         while let Some((x, y)) = camp.pop() {
-            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-                if map.read(x + dx, y + dy) == Some(Elevation(Some(digit))) {
-                    next.push((x + dx, y + dy));
+            for (pos, elevation) in map.orthogonal(x, y) {
+                if elevation == Some(Elevation(Some(digit))) {
+                    next.push(pos);
                 }
             }
         }
@@ -45,22 +51,37 @@ fn score(map: &Trails, x: isize, y: isize, unique: bool) -> u32 {
     camp.len() as u32
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Trails = ctxt.value().parse().unwrap();
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Trails = ctxt.map(ctxt.value())?;
     let mut sum = 0;
     for (x, y) in map.find(|loc| loc == Elevation(Some(0))) {
         sum += score(&map, x, y, true);
     }
-    println!("Sum of scores is: {sum}");
+    Ok(sum.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Trails = ctxt.value().parse().unwrap();
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Trails = ctxt.map(ctxt.value())?;
     let mut sum = 0;
     for (x, y) in map.find(|loc| loc == Elevation(Some(0))) {
         sum += score(&map, x, y, false);
     }
-    println!("Sum of ratings is: {sum}");
+    Ok(sum.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/10example"), Ok(Answer::Number(36)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/10example"), Ok(Answer::Number(81)));
+    }
 }
@@ -1,4 +1,5 @@
-use history::readfile;
+use history::memo::Memo;
+use history::{readfile, Answer, InputError};
 
 type Num = u64;
 
@@ -40,62 +41,61 @@ fn blink(before: &[Num]) -> Vec<Num> {
     after
 }
 
-use std::collections::HashMap;
-type Nums = HashMap<Num, Num>;
-
-fn faster(before: &Nums) -> Nums {
-    let mut after = HashMap::with_capacity(before.len());
-    for (&num, &count) in before {
+/// How many stones a single stone becomes after blinking this many times
+fn stones(memo: &mut Memo<(Num, u8), Num>, num: Num, blinks: u8) -> Num {
+    if blinks == 0 {
+        return 1;
+    }
+    memo.get_or((num, blinks), |memo| {
         if num == 0 {
-            *after.entry(1).or_default() += count;
-            continue;
-        }
-        if let Some((first, second)) = maybe_split(num) {
-            *after.entry(first).or_default() += count;
-            *after.entry(second).or_default() += count;
+            stones(memo, 1, blinks - 1)
+        } else if let Some((first, second)) = maybe_split(num) {
+            stones(memo, first, blinks - 1) + stones(memo, second, blinks - 1)
         } else {
-            *after.entry(num * 2024).or_default() += count;
+            stones(memo, num * 2024, blinks - 1)
         }
-    }
-    after
+    })
 }
 
-fn count(map: &Nums) -> Num {
-    let mut total = 0;
-    for &count in map.values() {
-        total += count;
-    }
-    total
-}
-
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut nums: Vec<Num> = ctxt
         .value()
         .split_ascii_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
+        .map(|s| ctxt.parse(s, "Stones should be engraved with numbers"))
+        .collect::<Result<_, _>>()?;
     for _ in 1..=25 {
         nums = blink(&nums);
     }
     let stones = nums.len();
-    println!("After 25 blinks I have {stones} stones");
+    Ok(stones.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let nums: Vec<Num> = ctxt
         .value()
         .split_ascii_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let mut map = HashMap::with_capacity(nums.len());
-    for n in nums {
-        *map.entry(n).or_default() += 1;
+        .map(|s| ctxt.parse(s, "Stones should be engraved with numbers"))
+        .collect::<Result<_, _>>()?;
+    let mut memo = Memo::new();
+    let count: Num = nums.into_iter().map(|n| stones(&mut memo, n, 75)).sum();
+    Ok(count.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/11example"), Ok(Answer::Number(55312)));
     }
-    for _ in 1..=75 {
-        map = faster(&map);
+
+    // No answer is published for 75 blinks of the example, this is the output of the
+    // current code, so it only notices if that changes
+    #[test]
+    fn regression_b() {
+        assert_eq!(b("test-data/11example"), Ok(Answer::Number(65601038650482)));
     }
-    let stones = count(&map);
-    println!("After 75 blinks I have {stones} stones");
 }
@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{parse, readfile, Answer, InputError, Mistake};
 
 type Num = i128;
 
@@ -9,18 +9,23 @@ struct Button {
 }
 
 impl Button {
-    fn read(prefix: &str, s: &str) -> Self {
-        let s = s
-            .strip_prefix(prefix)
-            .expect("Buttons should begin with the agreed prefix");
+    fn read<'t>(prefix: &str, s: &'t str) -> Result<Self, Mistake<'t>> {
+        let s = s.strip_prefix(prefix).ok_or(Mistake::new(
+            s,
+            "Buttons should begin with the agreed prefix",
+        ))?;
         let (x, y) = s
             .split_once(", ")
-            .expect("Buttons should have both X and Y");
-        let x = x.strip_prefix("X+").expect("X should begin X+");
-        let x: Num = x.parse().expect("Should be a number");
-        let y = y.strip_prefix("Y+").expect("Y should begin Y+");
-        let y: Num = y.parse().expect("Should be a number");
-        Self { x, y }
+            .ok_or(Mistake::new(s, "Buttons should have both X and Y"))?;
+        let x = x
+            .strip_prefix("X+")
+            .ok_or(Mistake::new(x, "X should begin X+"))?;
+        let x: Num = parse(x, "Should be a number")?;
+        let y = y
+            .strip_prefix("Y+")
+            .ok_or(Mistake::new(y, "Y should begin Y+"))?;
+        let y: Num = parse(y, "Should be a number")?;
+        Ok(Self { x, y })
     }
 }
 
@@ -30,42 +35,49 @@ struct Prize {
     y: Num,
 }
 
-use std::str::FromStr;
-impl FromStr for Prize {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Prize {
+    fn parse(s: &str) -> Result<Self, Mistake<'_>> {
         let s = s
             .strip_prefix("Prize: ")
-            .ok_or("Prizes should begin with Prize:")?;
+            .ok_or(Mistake::new(s, "Prizes should begin with Prize:"))?;
         let (x, y) = s
             .split_once(", ")
-            .ok_or("Prize should have two conditions")?;
-        let x = x.strip_prefix("X=").ok_or("X should begin X=")?;
-        let x: Num = x.parse().map_err(|_| "Should be a number")?;
-        let y = y.strip_prefix("Y=").ok_or("Y should begin Y=")?;
-        let y: Num = y.parse().map_err(|_| "Should be a number")?;
+            .ok_or(Mistake::new(s, "Prize should have two conditions"))?;
+        let x = x
+            .strip_prefix("X=")
+            .ok_or(Mistake::new(x, "X should begin X="))?;
+        let x: Num = parse(x, "Should be a number")?;
+        let y = y
+            .strip_prefix("Y=")
+            .ok_or(Mistake::new(y, "Y should begin Y="))?;
+        let y: Num = parse(y, "Should be a number")?;
         Ok(Prize { x, y })
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
     let mut tokens = 0;
     loop {
         let Some(first) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let a = Button::read("Button A: ", first);
+        let a = ctxt.locate(Button::read("Button A: ", first))?;
         let Some(second) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let b = Button::read("Button B: ", second);
+        let b = ctxt.locate(Button::read("Button B: ", second))?;
         let Some(third) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let prize: Prize = third.parse().expect("should be a Prize");
+        let prize = ctxt.locate(Prize::parse(third))?;
 
         let mut fewest: Option<Num> = None;
         for push_a in 0..=100 {
@@ -102,26 +114,32 @@ pub fn a(filename: &str) {
             break;
         }
     }
-    println!("Spent {tokens} to win all possible prizes");
+    Ok(tokens.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
     let mut tokens = 0;
     loop {
         let Some(first) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let a = Button::read("Button A: ", first);
+        let a = ctxt.locate(Button::read("Button A: ", first))?;
         let Some(second) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let b = Button::read("Button B: ", second);
+        let b = ctxt.locate(Button::read("Button B: ", second))?;
         let Some(third) = lines.next() else {
-            panic!("Should not run out of lines mid-way through an arcade machine");
+            return Err(
+                ctxt.missing("Should not run out of lines mid-way through an arcade machine")
+            );
         };
-        let mut prize: Prize = third.parse().expect("should be a Prize");
+        let mut prize = ctxt.locate(Prize::parse(third))?;
         prize.x += 10000000000000;
         prize.y += 10000000000000;
 
@@ -135,5 +153,22 @@ pub fn b(filename: &str) {
             break;
         }
     }
-    println!("Spent {tokens} to win all possible prizes");
+    Ok(tokens.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/13example"), Ok(Answer::Number(480)));
+    }
+
+    // No answer is published for the far-off prizes of the example, this is the output
+    // of the current code, so it only notices if that changes
+    #[test]
+    fn regression_b() {
+        assert_eq!(b("test-data/13example"), Ok(Answer::Number(875318608908)));
+    }
 }
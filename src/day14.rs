@@ -1,4 +1,8 @@
-use history::readfile;
+use history::animate;
+use history::cycle;
+use history::image::Colour;
+use history::map::Map;
+use history::{parse, readfile, Answer, InputError, Mistake};
 
 type Num = i32;
 
@@ -10,34 +14,29 @@ struct Robot {
     vy: Num,
 }
 
-use std::str::FromStr;
-impl FromStr for Robot {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Robot {
+    fn parse(s: &str) -> Result<Self, Mistake<'_>> {
         let (p, v) = s
             .split_once(' ')
-            .ok_or("Robots should have position and velocity")?;
-        let p = p.strip_prefix("p=").ok_or("Positions should begin p=")?;
-        let (x, y) = p
-            .split_once(',')
-            .ok_or("Positions should be x,y separated by a comma")?;
-        let x: Num = x
-            .parse()
-            .map_err(|_| "Numbers should fit in the agreed type")?;
-        let y: Num = y
-            .parse()
-            .map_err(|_| "Numbers should fit in the agreed type")?;
-        let v = v.strip_prefix("v=").ok_or("Velocities should begin v=")?;
-        let (vx, vy) = v
-            .split_once(',')
-            .ok_or("Velocities should be x,y separated by a comma")?;
-        let vx: Num = vx
-            .parse()
-            .map_err(|_| "Numbers should fit in the agreed type")?;
-        let vy: Num = vy
-            .parse()
-            .map_err(|_| "Numbers should fit in the agreed type")?;
+            .ok_or(Mistake::new(s, "Robots should have position and velocity"))?;
+        let p = p
+            .strip_prefix("p=")
+            .ok_or(Mistake::new(p, "Positions should begin p="))?;
+        let (x, y) = p.split_once(',').ok_or(Mistake::new(
+            p,
+            "Positions should be x,y separated by a comma",
+        ))?;
+        let x: Num = parse(x, "Numbers should fit in the agreed type")?;
+        let y: Num = parse(y, "Numbers should fit in the agreed type")?;
+        let v = v
+            .strip_prefix("v=")
+            .ok_or(Mistake::new(v, "Velocities should begin v="))?;
+        let (vx, vy) = v.split_once(',').ok_or(Mistake::new(
+            v,
+            "Velocities should be x,y separated by a comma",
+        ))?;
+        let vx: Num = parse(vx, "Numbers should fit in the agreed type")?;
+        let vy: Num = parse(vy, "Numbers should fit in the agreed type")?;
         Ok(Robot { x, y, vx, vy })
     }
 }
@@ -45,14 +44,9 @@ impl FromStr for Robot {
 const WIDTH: Num = 101;
 const LENGTH: Num = 103;
 
-#[derive(Copy, Clone, Debug)]
-enum Quadrant {
-    Middle,
-    TopLeft,
-    TopRight,
-    BottomLeft,
-    BottomRight,
-}
+/// Width and length of the room the robots are in
+type Room = (Num, Num);
+const BATHROOM: Room = (WIDTH, LENGTH);
 
 impl Robot {
     fn apply(z: Num, vz: Num, max: Num, seconds: Num) -> Num {
@@ -68,71 +62,120 @@ impl Robot {
         p
     }
 
-    fn simulate(&mut self, seconds: Num) {
-        self.x = Robot::apply(self.x, self.vx, WIDTH, seconds);
-        self.y = Robot::apply(self.y, self.vy, LENGTH, seconds);
+    fn simulate(&mut self, seconds: Num, (width, length): Room) {
+        self.x = Robot::apply(self.x, self.vx, width, seconds);
+        self.y = Robot::apply(self.y, self.vy, length, seconds);
     }
+}
 
-    fn quadrant(&self) -> Quadrant {
-        use std::cmp::Ordering::*;
-        use Quadrant::*;
-        match (self.x.cmp(&(WIDTH / 2)), self.y.cmp(&(LENGTH / 2))) {
-            (Less, Less) => TopLeft,
-            (Less, Greater) => TopRight,
-            (Greater, Less) => BottomLeft,
-            (Greater, Greater) => BottomRight,
-            (_, _) => Middle,
-        }
+/// Seconds until every robot is back where it started
+/// Each axis repeats on its own, so find those periods from just the positions along it
+fn period(robots: &[Robot], (width, length): Room) -> usize {
+    let along = |z: fn(&Robot) -> Num, vz: fn(&Robot) -> Num, max: Num| {
+        let initial: Vec<Num> = robots.iter().map(z).collect();
+        let step = |positions: &Vec<Num>| {
+            positions
+                .iter()
+                .zip(robots)
+                .map(|(&p, r)| Robot::apply(p, vz(r), max, 1))
+                .collect()
+        };
+        cycle::brent(&initial, step).period
+    };
+    let across = along(|r| r.x, |r| r.vx, width);
+    let down = along(|r| r.y, |r| r.vy, length);
+    // Least common multiple, by way of Euclid's greatest common divisor
+    let (mut a, mut b) = (across, down);
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+    across / a * down
 }
 
-fn safety(robots: &[Robot]) -> Num {
-    use Quadrant::*;
+/// How many robots are on a tile, as drawn in the puzzle
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct Tile(u8);
 
-    let mut tl = 0;
-    let mut tr = 0;
-    let mut bl = 0;
-    let mut br = 0;
+impl history::map::Legend for Tile {
+    fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
 
-    for r in robots {
-        match r.quadrant() {
-            TopLeft => tl += 1,
-            TopRight => tr += 1,
-            BottomLeft => bl += 1,
-            BottomRight => br += 1,
-            _ => (),
+    fn try_from_char(ch: char) -> Option<Self> {
+        match ch {
+            '.' => Some(Tile(0)),
+            '1'..='9' => Some(Tile(ch as u8 - b'0')),
+            _ => None,
         }
     }
 
-    tl * tr * bl * br
+    fn to_char(self) -> char {
+        match self.0 {
+            0 => '.',
+            n @ 1..=9 => (b'0' + n) as char,
+            _ => '*',
+        }
+    }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut v: Vec<Robot> = Vec::new();
-    for line in ctxt.lines() {
-        let mut r: Robot = line.parse().expect("Should be a robot");
-        r.simulate(100);
-        v.push(r);
+/// Robots are green on a black floor, brighter where more of them share a tile
+impl Colour for Tile {
+    fn to_rgb(self) -> [u8; 3] {
+        match self.0 {
+            0 => [0, 0, 0],
+            n => [0, 96 + 32 * n.min(4), 0],
+        }
     }
-    let safe = safety(&v);
-    println!("Safety factor after 100 seconds is: {safe}");
 }
 
-use history::map::Map;
+/// Where b saves the best arrangement it found while watching, to see in an image viewer
+const PICTURE: &str = "day14.png";
 
-#[allow(dead_code)] // Just for diagnostics
-fn display(robots: &[Robot]) {
-    let mut grid: Map<char> = Map::new();
-    for y in 0..LENGTH {
-        for x in 0..WIDTH {
-            grid.write(x as isize, y as isize, '.');
-        }
-    }
+fn picture(floor: &Map<Tile>, filename: &str) -> std::io::Result<()> {
+    let big = floor.scale(4, 4, |tile, _, _| tile);
+    let file = std::fs::File::create(filename)?;
+    big.write_png(std::io::BufWriter::new(file))
+}
+
+/// How many robots are on each tile of the floor, seconds from now
+fn floor(robots: &[Robot], (width, length): Room, seconds: Num) -> Map<u8> {
+    let mut floor: Map<u8> = Map::torus(width as isize, length as isize);
     for r in robots {
-        grid.write(r.x as isize, r.y as isize, '*');
+        let x = (r.x + r.vx * seconds) as isize;
+        let y = (r.y + r.vy * seconds) as isize;
+        let here = floor.read(x, y).unwrap_or_default();
+        floor.write(x, y, here + 1);
     }
-    println!("{grid}");
+    floor
+}
+
+/// Product of the robots in each quadrant, ignoring the middle row and column
+fn safety(floor: &Map<u8>) -> usize {
+    let (right, bottom) = (*floor.x().end(), *floor.y().end());
+    let (mx, my) = (right / 2, bottom / 2);
+    [
+        floor.window(0..=mx - 1, 0..=my - 1),
+        floor.window(mx + 1..=right, 0..=my - 1),
+        floor.window(0..=mx - 1, my + 1..=bottom),
+        floor.window(mx + 1..=right, my + 1..=bottom),
+    ]
+    .iter()
+    .map(|quadrant| quadrant.cells().map(|(_, n)| n as usize).sum::<usize>())
+    .product()
+}
+
+fn safety_after(filename: &str, room: Room, seconds: Num) -> Result<usize, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut v: Vec<Robot> = Vec::new();
+    for line in ctxt.lines() {
+        v.push(ctxt.locate(Robot::parse(line))?);
+    }
+    Ok(safety(&floor(&v, room, seconds)))
+}
+
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let safe = safety_after(filename, BATHROOM, 100)?;
+    Ok(safe.into())
 }
 
 fn entropy(robots: &[Robot]) -> Num {
@@ -153,30 +196,68 @@ fn entropy(robots: &[Robot]) -> Num {
     dx + dy
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut v: Vec<Robot> = Vec::new();
     for line in ctxt.lines() {
-        let r: Robot = line.parse().expect("Should be a robot");
+        let r = ctxt.locate(Robot::parse(line))?;
         v.push(r);
     }
 
-    // try WIDTH * LENGTH simulations
+    // Every arrangement of the robots happens within one period
+    let period = period(&v, BATHROOM) as Num;
+    let watch = animate::enabled();
     let mut best: Option<(Num, Num)> = None;
-    for step in 1..=(WIDTH * LENGTH) {
+    let mut frame: Option<Map<Tile>> = None;
+    for step in 1..=period {
         for r in v.iter_mut() {
-            r.simulate(1);
+            r.simulate(1, BATHROOM);
         }
         let entropy = entropy(&v);
-        if let Some((best_entropy, _)) = best {
-            if best_entropy > entropy {
-                best = Some((entropy, step));
-            }
-        } else {
+        if best.is_none_or(|(best_entropy, _)| best_entropy > entropy) {
             best = Some((entropy, step));
+            if watch {
+                let tiles = floor(&v, BATHROOM, 0).convert(Tile);
+                animate::show(&tiles);
+                frame = Some(tiles);
+            }
+        }
+    }
+    if let Some(frame) = frame {
+        match picture(&frame, PICTURE) {
+            Ok(()) => eprintln!("Saved the best arrangement as {PICTURE}"),
+            Err(e) => eprintln!("Couldn't save the best arrangement as {PICTURE}: {e}"),
         }
     }
-    if let Some((_, step)) = best {
-        println!("Robots display the Xmas tree after {step} seconds");
+    let (_, step) = best.expect("robots should have been simulated at least once");
+    Ok(step.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        assert_eq!(safety_after("test-data/14example", (11, 7), 100), Ok(12));
+    }
+
+    #[test]
+    fn teleport() {
+        let mut r = Robot::parse("p=2,4 v=2,-3").unwrap();
+        r.simulate(5, (11, 7));
+        assert_eq!((r.x, r.y), (1, 3));
+    }
+
+    #[test]
+    fn periods() {
+        let r = Robot::parse("p=2,4 v=2,-3").unwrap();
+        assert_eq!(period(&[r], (11, 7)), 77);
+        let still = Robot::parse("p=0,0 v=0,7").unwrap();
+        assert_eq!(period(&[still], (11, 7)), 1);
+        assert_eq!(
+            period(&[r, still], BATHROOM),
+            WIDTH as usize * LENGTH as usize
+        );
     }
 }
@@ -1,5 +1,6 @@
-use history::map::Map;
-use history::readfile;
+use history::animate;
+use history::map::{Direction, Map, Point};
+use history::{readfile, Answer, InputError, Mistake};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Legend {
@@ -12,12 +13,16 @@ enum Legend {
 
 impl history::map::Legend for Legend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '#' => Self::Wall,
-            '.' => Self::Space,
-            'O' => Self::Crate,
-            '@' => Self::Robot,
-            _ => panic!("Unexpected symbol on map"),
+            '#' => Some(Self::Wall),
+            '.' => Some(Self::Space),
+            'O' => Some(Self::Crate),
+            '@' => Some(Self::Robot),
+            _ => None,
         }
     }
 
@@ -33,30 +38,11 @@ impl history::map::Legend for Legend {
 
 type Warehouse = Map<Legend>;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn go(&self) -> (isize, isize) {
-        match self {
-            Up => (0, -1),
-            Down => (0, 1),
-            Left => (-1, 0),
-            Right => (1, 0),
-        }
-    }
-}
-
 use Direction::*;
 
 fn check(map: &Warehouse, mut x: isize, mut y: isize, dir: Direction) -> Option<(isize, isize)> {
     use Legend::*;
-    let (dx, dy) = dir.go();
+    let Point { x: dx, y: dy } = dir.offset();
     loop {
         x += dx;
         y += dy;
@@ -73,7 +59,7 @@ fn shove(map: &mut Warehouse, mut x: isize, mut y: isize, dir: Direction) -> (is
     use Legend::*;
     debug_assert_eq!(map.read(x, y), Some(Robot));
     if let Some((fx, fy)) = check(map, x, y, dir) {
-        let (dx, dy) = dir.go();
+        let Point { x: dx, y: dy } = dir.offset();
         map.write(x, y, Space);
         x += dx;
         y += dy;
@@ -97,31 +83,44 @@ fn gps(wide: &RangeInclusive<isize>, tall: &RangeInclusive<isize>, x: isize, y:
     y * 100 + x
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let (map, rest) = ctxt
         .value()
         .split_once("\n\n")
-        .expect("should have a map and a list of instructions");
-    let mut map: Warehouse = map.parse().unwrap();
+        .ok_or_else(|| ctxt.missing("should have a map and a list of instructions"))?;
+    let mut map: Warehouse = ctxt.map(map)?;
 
-    let (mut x, mut y) = map.find(|r| r == Legend::Robot)[0];
-    for ch in rest.chars() {
+    let robot = map.find(|r| r == Legend::Robot);
+    let &(mut x, mut y) = robot
+        .first()
+        .ok_or_else(|| ctxt.missing("The warehouse should have a robot"))?;
+    let watch = animate::enabled();
+    for (n, ch) in rest.char_indices() {
         match ch {
             '^' => {
-                (x, y) = shove(&mut map, x, y, Up);
+                (x, y) = shove(&mut map, x, y, North);
             }
             'v' => {
-                (x, y) = shove(&mut map, x, y, Down);
+                (x, y) = shove(&mut map, x, y, South);
             }
             '<' => {
-                (x, y) = shove(&mut map, x, y, Left);
+                (x, y) = shove(&mut map, x, y, West);
             }
             '>' => {
-                (x, y) = shove(&mut map, x, y, Right);
+                (x, y) = shove(&mut map, x, y, East);
             }
-            '\n' => (),
-            _ => panic!("Unexpected {ch} in instruction stream"),
+            '\n' => continue,
+            _ => {
+                let text = &rest[n..n + ch.len_utf8()];
+                return Err(ctxt.error(Mistake::new(
+                    text,
+                    "Unexpected symbol in instruction stream",
+                )));
+            }
+        }
+        if watch {
+            animate::highlight(&map, Some(Point::new(x, y)), &[]);
         }
     }
     let mut sum = 0;
@@ -130,7 +129,7 @@ pub fn a(filename: &str) {
     for (x, y) in map.find(|p| p == Legend::Crate) {
         sum += gps(&wide, &tall, x, y);
     }
-    println!("Sum of all boxes GPS co-ordinates was: {sum}");
+    Ok(sum.into())
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
@@ -145,13 +144,17 @@ enum BigLegend {
 
 impl history::map::Legend for BigLegend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '#' => Self::Wall,
-            '.' => Self::Space,
-            '[' => Self::LCrate,
-            ']' => Self::RCrate,
-            '@' => Self::Robot,
-            _ => panic!("Unexpected symbol on map"),
+            '#' => Some(Self::Wall),
+            '.' => Some(Self::Space),
+            '[' => Some(Self::LCrate),
+            ']' => Some(Self::RCrate),
+            '@' => Some(Self::Robot),
+            _ => None,
         }
     }
 
@@ -168,37 +171,6 @@ impl history::map::Legend for BigLegend {
 
 type BigWarehouse = Map<BigLegend>;
 
-fn scale(from: Warehouse) -> BigWarehouse {
-    let old_range = from.x();
-    let lx = *old_range.start();
-    let rx = *old_range.end();
-    let mut to: BigWarehouse = Map::ranged(lx..=(rx * 3), from.y());
-    for y in from.y() {
-        for x in from.x() {
-            match from.read(x, y) {
-                Some(Legend::Wall) => {
-                    to.write(x * 2, y, BigLegend::Wall);
-                    to.write(x * 2 + 1, y, BigLegend::Wall);
-                }
-                Some(Legend::Space) => {
-                    to.write(x * 2, y, BigLegend::Space);
-                    to.write(x * 2 + 1, y, BigLegend::Space);
-                }
-                Some(Legend::Crate) => {
-                    to.write(x * 2, y, BigLegend::LCrate);
-                    to.write(x * 2 + 1, y, BigLegend::RCrate);
-                }
-                Some(Legend::Robot) => {
-                    to.write(x * 2, y, BigLegend::Robot);
-                    to.write(x * 2 + 1, y, BigLegend::Space);
-                }
-                None => panic!("Within range we should not see None in map reads"),
-            }
-        }
-    }
-    to
-}
-
 fn big_horiz_check(
     map: &BigWarehouse,
     mut x: isize,
@@ -206,7 +178,7 @@ fn big_horiz_check(
     dir: Direction,
 ) -> Option<(isize, isize)> {
     use BigLegend::*;
-    let (dx, _) = dir.go();
+    let dx = dir.offset().x;
     loop {
         x += dx;
         match map.read(x, y).unwrap_or_default() {
@@ -311,7 +283,7 @@ fn big_shove(map: &mut BigWarehouse, mut x: isize, y: isize, dir: Direction) ->
     use BigLegend::*;
     debug_assert_eq!(map.read(x, y), Some(Robot));
     match dir {
-        Left => {
+        West => {
             if let Some((fx, _)) = big_horiz_check(map, x, y, dir) {
                 map.write(x, y, Space);
                 x -= 1;
@@ -327,7 +299,7 @@ fn big_shove(map: &mut BigWarehouse, mut x: isize, y: isize, dir: Direction) ->
                 (x, y)
             }
         }
-        Right => {
+        East => {
             if let Some((fx, _)) = big_horiz_check(map, x, y, dir) {
                 map.write(x, y, Space);
                 x += 1;
@@ -343,14 +315,14 @@ fn big_shove(map: &mut BigWarehouse, mut x: isize, y: isize, dir: Direction) ->
                 (x, y)
             }
         }
-        Up => {
+        North => {
             if attempt_vert(map, x, y, -1) {
                 (x, y - 1)
             } else {
                 (x, y)
             }
         }
-        Down => {
+        South => {
             if attempt_vert(map, x, y, 1) {
                 (x, y + 1)
             } else {
@@ -360,31 +332,51 @@ fn big_shove(map: &mut BigWarehouse, mut x: isize, y: isize, dir: Direction) ->
     }
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let (map, rest) = ctxt
         .value()
         .split_once("\n\n")
-        .expect("should have a map and a list of instructions");
-    let small: Warehouse = map.parse().unwrap();
-    let mut map = scale(small);
-    let (mut x, mut y) = map.find(|r| r == BigLegend::Robot)[0];
-    for ch in rest.chars() {
+        .ok_or_else(|| ctxt.missing("should have a map and a list of instructions"))?;
+    let small: Warehouse = ctxt.map(map)?;
+    // Everything except the robot is twice as wide
+    let mut map: BigWarehouse = small.scale(2, 1, |cell, dx, _| match (cell, dx) {
+        (Legend::Wall, _) => BigLegend::Wall,
+        (Legend::Space, _) | (Legend::Robot, 1) => BigLegend::Space,
+        (Legend::Crate, 0) => BigLegend::LCrate,
+        (Legend::Crate, _) => BigLegend::RCrate,
+        (Legend::Robot, _) => BigLegend::Robot,
+    });
+    let robot = map.find(|r| r == BigLegend::Robot);
+    let &(mut x, mut y) = robot
+        .first()
+        .ok_or_else(|| ctxt.missing("The warehouse should have a robot"))?;
+    let watch = animate::enabled();
+    for (n, ch) in rest.char_indices() {
         match ch {
             '^' => {
-                (x, y) = big_shove(&mut map, x, y, Up);
+                (x, y) = big_shove(&mut map, x, y, North);
             }
             'v' => {
-                (x, y) = big_shove(&mut map, x, y, Down);
+                (x, y) = big_shove(&mut map, x, y, South);
             }
             '<' => {
-                (x, y) = big_shove(&mut map, x, y, Left);
+                (x, y) = big_shove(&mut map, x, y, West);
             }
             '>' => {
-                (x, y) = big_shove(&mut map, x, y, Right);
+                (x, y) = big_shove(&mut map, x, y, East);
+            }
+            '\n' => continue,
+            _ => {
+                let text = &rest[n..n + ch.len_utf8()];
+                return Err(ctxt.error(Mistake::new(
+                    text,
+                    "Unexpected symbol in instruction stream",
+                )));
             }
-            '\n' => (),
-            _ => panic!("Unexpected {ch} in instruction stream"),
+        }
+        if watch {
+            animate::highlight(&map, Some(Point::new(x, y)), &[]);
         }
     }
 
@@ -394,5 +386,47 @@ pub fn b(filename: &str) {
     for (x, y) in map.find(|p| p == BigLegend::LCrate) {
         sum += gps(&wide, &tall, x, y);
     }
-    println!("In wider warehouse, sum of all boxes GPS co-ordinates was: {sum}");
+    Ok(sum.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_warehouse() {
+        let ctxt = readfile("test-data/15example").unwrap();
+        let (map, moves) = ctxt.value().split_once("\n\n").unwrap();
+        let mut map: Warehouse = map.parse().unwrap();
+        let (mut x, mut y) = map.find(|r| r == Legend::Robot)[0];
+        for dir in moves.chars().filter_map(Direction::from_char) {
+            (x, y) = shove(&mut map, x, y, dir);
+        }
+        let expected: Warehouse = "########\n\
+                                   #....OO#\n\
+                                   ##.....#\n\
+                                   #.....O#\n\
+                                   #.#O@..#\n\
+                                   #...O..#\n\
+                                   #...O..#\n\
+                                   ########\n"
+            .parse()
+            .unwrap();
+        assert!(map == expected, "\n{}", map.compare(&expected));
+    }
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/15example"), Ok(Answer::Number(2028)));
+    }
+
+    #[test]
+    fn larger_a() {
+        assert_eq!(a("test-data/15larger"), Ok(Answer::Number(10092)));
+    }
+
+    #[test]
+    fn larger_b() {
+        assert_eq!(b("test-data/15larger"), Ok(Answer::Number(9021)));
+    }
 }
@@ -1,5 +1,6 @@
-use history::map::Map;
-use history::readfile;
+use history::map::{Direction, Map, Point};
+use history::{readfile, Answer, Contents, InputError, State};
+use std::collections::HashSet;
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Legend {
@@ -12,12 +13,16 @@ enum Legend {
 
 impl history::map::Legend for Legend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '#' => Self::Wall,
-            '.' => Self::Space,
-            'S' => Self::Start,
-            'E' => Self::End,
-            _ => panic!("Unexpected symbol on map"),
+            '#' => Some(Self::Wall),
+            '.' => Some(Self::Space),
+            'S' => Some(Self::Start),
+            'E' => Some(Self::End),
+            _ => None,
         }
     }
 
@@ -31,252 +36,114 @@ impl history::map::Legend for Legend {
     }
 }
 
-type Points = u32;
 type Maze = Map<Legend>;
-type Scores = Map<Option<Points>>;
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-enum Direction {
-    North,
-    South,
-    West,
-    East,
-}
-
-impl Direction {
-    fn go(&self) -> (isize, isize) {
-        match self {
-            North => (0, -1),
-            South => (0, 1),
-            West => (-1, 0),
-            East => (1, 0),
-        }
-    }
-
-    fn turn_clock(self) -> Self {
-        match self {
-            North => East,
-            South => West,
-            West => North,
-            East => South,
-        }
-    }
-
-    fn turn_anti(self) -> Self {
-        match self {
-            North => West,
-            South => East,
-            West => South,
-            East => North,
-        }
-    }
-}
-
-use Direction::*;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 struct Reindeer {
-    x: isize,
-    y: isize,
+    pos: Point,
     head: Direction,
-    score: Points,
 }
 
 impl Reindeer {
-    fn start((x, y): (isize, isize)) -> Self {
+    fn start(pos: Point) -> Self {
         Self {
-            x,
-            y,
-            head: East,
-            score: 0,
+            pos,
+            head: Direction::East,
         }
     }
 }
 
-fn flood(map: &Maze) -> Option<Points> {
-    let start = map.find(|p| p == Legend::Start)[0];
-    let end = map.find(|p| p == Legend::End)[0];
-    let init = Reindeer::start(start);
-    let mut best: Scores = Map::new();
-    let mut this: Vec<Reindeer> = Vec::new();
-    this.push(init);
-    let mut lowest: Option<Points> = None;
-    loop {
-        let mut next: Vec<Reindeer> = Vec::with_capacity(this.len());
-        while let Some(mut deer) = this.pop() {
-            if lowest.is_some_and(|p| p <= deer.score) {
-                // This deer can't be a lowest scorer
-                continue;
-            }
-            // We always either move or turn & move, never just turn, so score the move here
-            deer.score += 1;
-            let (dx, dy) = deer.head.go();
-            match map.read(deer.x + dx, deer.y + dy).unwrap_or_default() {
-                Legend::Space | Legend::End => {
-                    let prev = best.read(deer.x + dx, deer.y + dy).unwrap_or_default();
-                    if prev.is_none_or(|p| p > deer.score) {
-                        let mut new = deer;
-                        new.x += dx;
-                        new.y += dy;
-                        next.push(new);
-                        best.write(new.x, new.y, Some(new.score));
-                    }
-                }
-                _ => (),
-            }
-            // Maybe turn 90°
-            deer.score += 1000;
-            let clock = deer.head.turn_clock();
-            let anti = deer.head.turn_anti();
-            for d in [clock, anti] {
-                let (dx, dy) = d.go();
-                match map.read(deer.x + dx, deer.y + dy).unwrap_or_default() {
-                    Legend::Space | Legend::End => {
-                        let prev = best.read(deer.x + dx, deer.y + dy).unwrap_or_default();
-                        if prev.is_none_or(|p| p > deer.score) {
-                            let mut new = deer;
-                            new.head = d;
-                            new.x += dx;
-                            new.y += dy;
-                            next.push(new);
-                            best.write(new.x, new.y, Some(new.score));
-                        }
-                    }
-                    _ => (),
-                }
+impl State<Maze> for Reindeer {
+    fn describe(&self, _map: &Maze) -> String {
+        format!(
+            "{x}.{y} facing {head:?}",
+            x = self.pos.x,
+            y = self.pos.y,
+            head = self.head
+        )
+    }
+
+    fn next(&self, map: &Maze) -> Vec<Self> {
+        self.weighted(map)
+            .into_iter()
+            .map(|(deer, _)| deer)
+            .collect()
+    }
+
+    /// Step forward for 1 point, or turn 90° for 1000 points
+    fn weighted(&self, map: &Maze) -> Vec<(Self, usize)> {
+        let mut v = Vec::with_capacity(3);
+        let ahead = self.pos + self.head;
+        match map.read_at(ahead).unwrap_or_default() {
+            Legend::Space | Legend::End | Legend::Start => {
+                v.push((
+                    Reindeer {
+                        pos: ahead,
+                        ..*self
+                    },
+                    1,
+                ));
             }
+            Legend::Wall => (),
         }
-        lowest = best.read(end.0, end.1).unwrap_or_default();
-        if next.is_empty() {
-            break;
+        for head in [self.head.clockwise(), self.head.anticlockwise()] {
+            v.push((Reindeer { head, ..*self }, 1000));
         }
-        this = next;
+        v
     }
-    lowest
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Maze = ctxt.value().parse().unwrap();
-    let lowest = flood(&map).expect("there should be a route to the end");
-    println!("Lowest score a reindeer could get is: {lowest}");
+/// Where the maze starts and ends
+fn ends(ctxt: &Contents, map: &Maze) -> Result<(Point, Point), InputError> {
+    let start = map.find_points(|p| p == Legend::Start);
+    let start = start
+        .first()
+        .ok_or_else(|| ctxt.missing("The maze should have a start"))?;
+    let end = map.find_points(|p| p == Legend::End);
+    let end = end
+        .first()
+        .ok_or_else(|| ctxt.missing("The maze should have an end"))?;
+    Ok((*start, *end))
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct TrackedReindeer {
-    x: isize,
-    y: isize,
-    head: Direction,
-    score: Points,
-    been: Vec<(isize, isize)>,
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Maze = ctxt.map(ctxt.value())?;
+    let (start, end) = ends(&ctxt, &map)?;
+    let lowest = State::cheapest(Reindeer::start(start), |r| r.pos == end, &map)
+        .ok_or_else(|| ctxt.missing("There should be a route to the end"))?;
+    Ok(lowest.into())
 }
 
-impl TrackedReindeer {
-    fn start((x, y): (isize, isize)) -> Self {
-        let been: Vec<(isize, isize)> = vec![(x, y)];
-        Self {
-            x,
-            y,
-            head: East,
-            score: 0,
-            been,
-        }
-    }
+/// Tiles which are on at least one of the best routes to the end
+fn tiles(map: &Maze, start: Point, end: Point) -> usize {
+    let Some(optimal) = State::optimal(Reindeer::start(start), |r| r.pos == end, map) else {
+        // Too bad, no routes to the end
+        return 0;
+    };
+    let tiles: HashSet<Point> = optimal.states().iter().map(|r| r.pos).collect();
+    tiles.len()
 }
 
-fn tiles(map: &Maze) -> usize {
-    let start = map.find(|p| p == Legend::Start)[0];
-    let end = map.find(|p| p == Legend::End)[0];
-    let init = TrackedReindeer::start(start);
-    let mut best: Scores = Map::new();
-    let mut this: Vec<TrackedReindeer> = Vec::new();
-    this.push(init);
-    let mut lowest: Option<Points> = None;
-    let mut routes: Vec<TrackedReindeer> = Vec::new();
-    loop {
-        let mut next: Vec<TrackedReindeer> = Vec::with_capacity(this.len());
-        while let Some(mut deer) = this.pop() {
-            if deer.x == end.0 && deer.y == end.1 {
-                routes.push(deer);
-                continue;
-            }
-            if lowest.is_some_and(|p| p <= deer.score) {
-                // This deer can't be a lowest scorer
-                continue;
-            }
-            // We always either move or turn & move, never just turn, so score the move here
-            deer.score += 1;
-            let (dx, dy) = deer.head.go();
-            match map.read(deer.x + dx, deer.y + dy).unwrap_or_default() {
-                Legend::Space | Legend::End => {
-                    let prev = best.read(deer.x + dx, deer.y + dy).unwrap_or_default();
-                    if prev.is_none_or(|p| p >= deer.score) {
-                        let mut new = deer.clone();
-                        new.x += dx;
-                        new.y += dy;
-                        new.been.push((new.x, new.y));
-                        best.write(new.x, new.y, Some(new.score));
-                        next.push(new);
-                    } else {
-                        // Maybe we're not turning here but others did so their score was lower but
-                        // soon it won't be?
-                        let mut new = deer.clone();
-                        new.x += dx;
-                        new.y += dy;
-                        new.been.push((new.x, new.y));
-                        next.push(new);
-                    }
-                }
-                _ => (),
-            }
-            // Maybe turn 90°
-            deer.score += 1000;
-            let clock = deer.head.turn_clock();
-            let anti = deer.head.turn_anti();
-            for d in [clock, anti] {
-                let (dx, dy) = d.go();
-                match map.read(deer.x + dx, deer.y + dy).unwrap_or_default() {
-                    Legend::Space | Legend::End => {
-                        let prev = best.read(deer.x + dx, deer.y + dy).unwrap_or_default();
-                        if prev.is_none_or(|p| p >= deer.score) {
-                            let mut new = deer.clone();
-                            new.head = d;
-                            new.x += dx;
-                            new.y += dy;
-                            new.been.push((new.x, new.y));
-                            best.write(new.x, new.y, Some(new.score));
-                            next.push(new);
-                        }
-                    }
-                    _ => (),
-                }
-            }
-        }
-        lowest = best.read(end.0, end.1).unwrap_or_default();
-        if next.is_empty() {
-            break;
-        }
-        this = next;
-    }
-    if let Some(distance) = lowest {
-        let mut good: Map<bool> = Map::new();
-        for deer in routes {
-            if deer.score == distance {
-                for (x, y) in deer.been {
-                    good.write(x, y, true);
-                }
-            }
-        }
-        good.count(|&&b| b)
-    } else {
-        // Too bad, no routes to the end
-        0
-    }
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Maze = ctxt.map(ctxt.value())?;
+    let (start, end) = ends(&ctxt, &map)?;
+    let count = tiles(&map, start, end);
+    Ok(count.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let map: Maze = ctxt.value().parse().unwrap();
-    let count = tiles(&map);
-    println!("{count} tiles are part of at least one of the best paths");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/16example"), Ok(Answer::Number(7036)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/16example"), Ok(Answer::Number(45)));
+    }
 }
@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{parse, readfile, Answer, InputError, Mistake};
 
 type Reg = u64;
 
@@ -11,49 +11,41 @@ struct Device {
     prog: Vec<u8>,
 }
 
-use std::str::FromStr;
-impl FromStr for Device {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Device {
+    fn parse(s: &str) -> Result<Self, Mistake<'_>> {
         let mut lines = s.lines();
-
-        let Some(a) = lines.next() else {
-            return Err("Should define register A");
-        };
-        let a = a
-            .strip_prefix("Register A: ")
-            .ok_or("Should define register A")?;
-        let a: Reg = a.parse().map_err(|_| "Should be a number")?;
-
-        let Some(b) = lines.next() else {
-            return Err("Should define register B");
-        };
-        let b = b
-            .strip_prefix("Register B: ")
-            .ok_or("Should define register A")?;
-        let b: Reg = b.parse().map_err(|_| "Should be a number")?;
-
-        let Some(c) = lines.next() else {
-            return Err("Should define register C");
-        };
-        let c = c
-            .strip_prefix("Register C: ")
-            .ok_or("Should define register C")?;
-        let c: Reg = c.parse().map_err(|_| "Should be a number")?;
-
-        if lines.next().is_none_or(|line| !line.is_empty()) {
-            return Err("Should have a blank separator line");
+        let a = Self::register(lines.next(), s, "Register A: ")?;
+        let b = Self::register(lines.next(), s, "Register B: ")?;
+        let c = Self::register(lines.next(), s, "Register C: ")?;
+
+        match lines.next() {
+            Some("") => (),
+            Some(line) => return Err(Mistake::new(line, "Should have a blank separator line")),
+            None => {
+                return Err(Mistake::new(
+                    &s[s.len()..],
+                    "Should have a blank separator line",
+                ))
+            }
         }
 
         let Some(prog) = lines.next() else {
-            return Err("Should define the program");
+            return Err(Mistake::new(&s[s.len()..], "Should define the program"));
         };
-
         let prog = prog
             .strip_prefix("Program: ")
-            .ok_or("Should prefix the program properly")?;
-        let prog: Vec<u8> = prog.split(',').map(|s| s.parse().unwrap()).collect();
+            .ok_or(Mistake::new(prog, "Should prefix the program properly"))?;
+        let prog = prog
+            .split(',')
+            .map(|text| {
+                let n: u8 = parse(text, "Program should be a list of numbers")?;
+                if n < 8 {
+                    Ok(n)
+                } else {
+                    Err(Mistake::new(text, "Program should only have 3-bit numbers"))
+                }
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
 
         Ok(Device {
             a,
@@ -63,6 +55,25 @@ impl FromStr for Device {
             prog,
         })
     }
+
+    /// Initial value for a register, from a line like "Register A: 729"
+    fn register<'t>(
+        line: Option<&'t str>,
+        s: &'t str,
+        prefix: &'static str,
+    ) -> Result<Reg, Mistake<'t>> {
+        let Some(line) = line else {
+            return Err(Mistake::new(
+                &s[s.len()..],
+                "Should define all three registers",
+            ));
+        };
+        let n = line.strip_prefix(prefix).ok_or(Mistake::new(
+            line,
+            "Should define registers A, B and C in order",
+        ))?;
+        parse(n, "Registers should hold a number")
+    }
 }
 
 impl Device {
@@ -121,7 +132,7 @@ impl Device {
                 self.c = self.a >> self.combo(operand);
                 None
             }
-            _ => panic!("Unexpected instruction {instruction}"),
+            _ => unreachable!("Parsing only allows 3-bit instructions, not {instruction}"),
         }
     }
 
@@ -142,11 +153,11 @@ fn list(nums: &[u8]) -> String {
     output
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut device: Device = ctxt.value().parse().expect("Should define the device");
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut device = ctxt.locate(Device::parse(ctxt.value()))?;
     let output = list(&device.run());
-    println!("Output joined by commas: {output}");
+    Ok(output.into())
 }
 
 fn check(a: &[u8], b: &[u8], steps: usize) -> bool {
@@ -167,9 +178,9 @@ fn check(a: &[u8], b: &[u8], steps: usize) -> bool {
     true
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut device: Device = ctxt.value().parse().expect("Should define the device");
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut device = ctxt.locate(Device::parse(ctxt.value()))?;
     let desired = device.prog.clone();
 
     let mut possible: Vec<Reg> = vec![0];
@@ -186,12 +197,47 @@ pub fn b(filename: &str) {
             }
         }
         if next.is_empty() {
-            panic!("Impossible to find a plausible input");
+            return Err(ctxt.overall(format!(
+                "No input makes the program output its first {} values",
+                step + 1
+            )));
         }
         next.sort_unstable();
         next.dedup();
         possible = next;
     }
     let first = possible[0];
-    println!("Lowest possible initial value of register A is {first}");
+    Ok(first.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(
+            a("test-data/17example"),
+            Ok(Answer::from("4,6,3,5,6,3,5,2,1,0"))
+        );
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/17quine"), Ok(Answer::Number(117440)));
+    }
+
+    #[test]
+    fn no_quine() {
+        assert!(b("test-data/17example").is_err());
+    }
+
+    #[test]
+    fn bad_opcode() {
+        let s = "Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,8,2\n";
+        let Err(mistake) = Device::parse(s) else {
+            panic!("8 is not an instruction");
+        };
+        assert_eq!(mistake.text, "8");
+    }
 }
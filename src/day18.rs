@@ -1,6 +1,6 @@
 use history::map::Map;
-use history::readfile;
 use history::State;
+use history::{parse, readfile, Answer, Contents, InputError, Mistake};
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Byte {
@@ -11,10 +11,14 @@ enum Byte {
 
 impl history::map::Legend for Byte {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '#' => Self::Corrupted,
-            '.' => Self::Empty,
-            _ => panic!("Unexpected '{ch}"),
+            '#' => Some(Self::Corrupted),
+            '.' => Some(Self::Empty),
+            _ => None,
         }
     }
 
@@ -31,10 +35,10 @@ type Memory = Map<Byte>;
 const EXIT: (isize, isize) = (70, 70);
 const STOP: usize = 1024;
 
-fn init() -> Memory {
+fn init(exit: (isize, isize)) -> Memory {
     let mut map = Map::new();
-    for y in 0..=EXIT.1 {
-        for x in 0..=EXIT.0 {
+    for y in 0..=exit.1 {
+        for x in 0..=exit.0 {
             map.write(x, y, Byte::Empty);
         }
     }
@@ -53,97 +57,121 @@ impl State<Memory> for Historians {
     }
 
     fn next(&self, map: &Memory) -> Vec<Self> {
-        let mut v = Vec::new();
-        for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
-            if map.read(self.x + dx, self.y + dy).unwrap_or_default() == Byte::Empty {
-                v.push(Historians {
-                    x: self.x + dx,
-                    y: self.y + dy,
-                });
-            }
-        }
-        v
+        map.orthogonal(self.x, self.y)
+            .filter(|&(_, byte)| byte == Some(Byte::Empty))
+            .map(|((x, y), _)| Historians { x, y })
+            .collect()
     }
 }
 
-fn read_coords(line: &str) -> (isize, isize) {
-    let (x, y) = line.split_once(',').expect("should be two co-ordinates");
-    let x: isize = x.parse().expect("X should be a number");
-    let y: isize = y.parse().expect("Y should be a number");
-    if x > EXIT.0 || y > EXIT.1 {
-        panic!("Inputs for real data maybe? Check EXIT and STOP constants");
+/// Fewest steps from the top left to the exit, if it's reachable at all
+fn escape(map: &Memory, exit: (isize, isize)) -> Option<usize> {
+    let start = Historians { x: 0, y: 0 };
+    let manhattan = |h: &Historians| exit.0.abs_diff(h.x) + exit.1.abs_diff(h.y);
+    let reached = |h: &Historians| (h.x, h.y) == exit;
+    State::astar(start, reached, manhattan, map).map(|(steps, _)| steps)
+}
+
+fn read_coords(line: &str, exit: (isize, isize)) -> Result<(isize, isize), Mistake<'_>> {
+    let (x, y) = line
+        .split_once(',')
+        .ok_or(Mistake::new(line, "should be two co-ordinates"))?;
+    let x: isize = parse(x, "X should be a number")?;
+    let y: isize = parse(y, "Y should be a number")?;
+    if x > exit.0 || y > exit.1 {
+        return Err(Mistake::new(
+            line,
+            "Inputs for real data maybe? Check EXIT and STOP constants",
+        ));
     }
-    (x, y)
+    Ok((x, y))
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut lines = ctxt.lines();
-    let mut map: Memory = init();
-    for _ in 0..STOP {
-        let line = lines.next().expect("should be a line of input");
-        let (x, y) = read_coords(line);
+/// Memory after the first stop bytes have fallen
+fn fallen<'t>(
+    ctxt: &Contents,
+    lines: &mut impl Iterator<Item = &'t str>,
+    exit: (isize, isize),
+    stop: usize,
+) -> Result<Memory, InputError> {
+    let mut map: Memory = init(exit);
+    for _ in 0..stop {
+        let line = lines
+            .next()
+            .ok_or_else(|| ctxt.missing("should be a line of input"))?;
+        let (x, y) = ctxt.locate(read_coords(line, exit))?;
         map.write(x, y, Byte::Corrupted);
     }
-    let start = Historians { x: 0, y: 0 };
-    let end = Historians {
-        x: EXIT.0,
-        y: EXIT.1,
-    };
-
-    if let Some(steps) = State::best(start, end, &map) {
-        println!("At least {steps} steps to reach the exit");
-    } else {
-        println!("Impossible to reach end after {STOP} bytes");
-    }
+    Ok(map)
+}
+
+/// Fewest steps to the exit after stop bytes have fallen
+fn shortest(filename: &str, exit: (isize, isize), stop: usize) -> Result<usize, InputError> {
+    let ctxt = readfile(filename)?;
+    let map = fallen(&ctxt, &mut ctxt.lines(), exit, stop)?;
+    escape(&map, exit).ok_or_else(|| ctxt.missing("should be possible to reach the exit"))
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    Ok(shortest(filename, EXIT, STOP)?.into())
+}
+
+/// Co-ordinates of the first byte after stop which makes the exit unreachable
+fn blocker(filename: &str, exit: (isize, isize), stop: usize) -> Result<String, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
-    let mut map: Memory = init();
-    for _ in 0..STOP {
-        let line = lines.next().expect("should be a line of input");
-        let (x, y) = line.split_once(',').expect("should be two co-ordinates");
-        let x: isize = x.parse().expect("X should be a number");
-        let y: isize = y.parse().expect("Y should be a number");
-        if x > EXIT.0 || y > EXIT.1 {
-            panic!("Inputs for real data maybe? Check EXIT and STOP constants");
-        }
-        map.write(x, y, Byte::Corrupted);
-    }
+    let mut map = fallen(&ctxt, &mut lines, exit, stop)?;
 
     let mut coords: Vec<&str> = Vec::new();
     let mut maps: Vec<Memory> = Vec::new();
     for line in lines {
-        let (x, y) = read_coords(line);
+        let (x, y) = ctxt.locate(read_coords(line, exit))?;
         map.write(x, y, Byte::Corrupted);
         maps.push(map.clone());
         coords.push(line);
     }
 
-    // There is at least one byte corrupted after we STOP in part A
-    assert!(!maps.is_empty());
+    if maps.is_empty() {
+        return Err(ctxt.missing("should be more bytes than fall in part a"));
+    }
     let mut first = 0;
     let mut last = maps.len() - 1;
 
     while first < last {
         let mid = (first + last) / 2;
-        let start = Historians { x: 0, y: 0 };
-        let end = Historians {
-            x: EXIT.0,
-            y: EXIT.1,
-        };
-
-        if State::best(start, end, &maps[mid]).is_some() {
+        if escape(&maps[mid], exit).is_some() {
             first = mid + 1;
         } else {
             last = mid;
         }
     }
 
-    println!(
-        "Co-ordinates of the first byte to make the exit unreachable: {}",
-        coords[first]
-    );
+    Ok(coords[first].to_owned())
+}
+
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    Ok(blocker(filename, EXIT, STOP)?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(shortest("test-data/18example", (6, 6), 12), Ok(22));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(
+            blocker("test-data/18example", (6, 6), 12),
+            Ok("6,1".to_owned())
+        );
+    }
+
+    #[test]
+    fn all_fallen() {
+        assert!(blocker("test-data/18example", (6, 6), 25).is_err());
+    }
 }
@@ -1,4 +1,5 @@
-use history::readfile;
+use history::memo::Memo;
+use history::{readfile, Answer, InputError};
 
 fn brands(line: &str) -> Vec<&str> {
     line.split(", ").collect()
@@ -32,41 +33,64 @@ fn attempt(pat: &str, from: &[&str]) -> bool {
     false
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
 
-    let towels = lines.next().expect("Should begin with a list of towels");
+    let towels = lines
+        .next()
+        .ok_or_else(|| ctxt.missing("Should begin with a list of towels"))?;
     let towels = brands(towels);
-    lines.next().expect("Should then have a blank link");
+    lines
+        .next()
+        .ok_or_else(|| ctxt.missing("Should then have a blank line"))?;
     let possible = lines.filter(|line| attempt(line, &towels)).count();
-    println!("{possible} designs are possible");
+    Ok(possible.into())
 }
 
-fn count_attempts(pat: &str, from: &[&str]) -> usize {
-    let mut counts: Vec<usize> = Vec::with_capacity(pat.len() + 1);
-    counts.push(1);
-    counts.resize(pat.len() + 1, 0);
-
-    for prefix in 0..pat.len() {
-        let count = counts[prefix];
-        let (_, right) = pat.split_at(prefix);
-        for towel in from {
-            if right.starts_with(towel) {
-                counts[prefix + towel.len()] += count;
-            }
-        }
+/// Ways to make pat from the towels, remembering the ways for each remainder of a pattern,
+/// which different patterns often share
+fn count_attempts<'p>(memo: &mut Memo<&'p str, usize>, pat: &'p str, from: &[&str]) -> usize {
+    if pat.is_empty() {
+        return 1;
     }
-    counts[pat.len()]
+    memo.get_or(pat, |memo| {
+        from.iter()
+            .filter_map(|towel| pat.strip_prefix(towel))
+            .map(|rest| count_attempts(memo, rest, from))
+            .sum()
+    })
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
 
-    let towels = lines.next().expect("Should begin with a list of towels");
+    let towels = lines
+        .next()
+        .ok_or_else(|| ctxt.missing("Should begin with a list of towels"))?;
     let towels = brands(towels);
-    lines.next().expect("Should then have a blank link");
-    let sum: usize = lines.map(|line| count_attempts(line, &towels)).sum();
-    println!("The designs could be made in total {sum} ways");
+    lines
+        .next()
+        .ok_or_else(|| ctxt.missing("Should then have a blank line"))?;
+    let mut memo = Memo::new();
+    let sum: usize = lines
+        .map(|line| count_attempts(&mut memo, line, &towels))
+        .sum();
+    Ok(sum.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/19example"), Ok(Answer::Number(6)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/19example"), Ok(Answer::Number(16)));
+    }
 }
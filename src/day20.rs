@@ -1,7 +1,7 @@
-use history::map::Map;
-use history::readfile;
+use history::map::{Map, Point};
+use history::{readfile, Answer, Contents, InputError};
 
-type Distance = u16;
+type Distance = u32;
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum Legend {
@@ -10,17 +10,20 @@ enum Legend {
     Space,
     Start,
     End,
-    Route(Distance),
 }
 
 impl history::map::Legend for Legend {
     fn from_char(ch: char) -> Self {
+        Self::try_from_char(ch).unwrap_or_else(|| panic!("Unexpected '{ch}' on map"))
+    }
+
+    fn try_from_char(ch: char) -> Option<Self> {
         match ch {
-            '#' => Self::Wall,
-            '.' => Self::Space,
-            'S' => Self::Start,
-            'E' => Self::End,
-            _ => panic!("Unexpected symbol on map"),
+            '#' => Some(Self::Wall),
+            '.' => Some(Self::Space),
+            'S' => Some(Self::Start),
+            'E' => Some(Self::End),
+            _ => None,
         }
     }
 
@@ -30,135 +33,113 @@ impl history::map::Legend for Legend {
             Self::Space => '.',
             Self::Start => 'S',
             Self::End => 'E',
-            Self::Route(_) => '*',
         }
     }
 }
 
 type Maze = Map<Legend>;
 
-fn obvious(map: &mut Maze) {
-    let start = map.find(|p| p == Legend::Start)[0];
-    let end = map.find(|p| p == Legend::End)[0];
-
-    let mut d: Distance = 0;
-    let mut pos = start;
-    while pos != end {
-        map.write(pos.0, pos.1, Legend::Route(d));
-        let left = map.read(pos.0 - 1, pos.1).unwrap_or_default();
-        let right = map.read(pos.0 + 1, pos.1).unwrap_or_default();
-        let up = map.read(pos.0, pos.1 - 1).unwrap_or_default();
-        let down = map.read(pos.0, pos.1 + 1).unwrap_or_default();
-        d += 1;
-        match (left, right, up, down) {
-            (Legend::Space | Legend::End, _, _, _) => {
-                pos.0 -= 1;
-            }
-            (_, Legend::Space | Legend::End, _, _) => {
-                pos.0 += 1;
-            }
-            (_, _, Legend::Space | Legend::End, _) => {
-                pos.1 -= 1;
-            }
-            (_, _, _, Legend::Space | Legend::End) => {
-                pos.1 += 1;
-            }
-            _ => panic!("Ran out of maze without finding exit"),
-        }
-    }
-    map.write(pos.0, pos.1, Legend::Route(d));
+/// Where the racetrack starts and ends
+fn ends(ctxt: &Contents, map: &Maze) -> Result<(Point, Point), InputError> {
+    let start = map.find_points(|p| p == Legend::Start);
+    let start = start
+        .first()
+        .ok_or_else(|| ctxt.missing("The racetrack should have a start"))?;
+    let end = map.find_points(|p| p == Legend::End);
+    let end = end
+        .first()
+        .ok_or_else(|| ctxt.missing("The racetrack should have an end"))?;
+    Ok((*start, *end))
 }
 
-fn skip(a: Distance, b: Distance, v: &mut Vec<Distance>) {
-    let d = a.abs_diff(b);
-    if d > 2 {
-        v.push(d - 2);
-    }
-}
+/// Time saved by each cheat lasting up to limit steps which saves any time at all
+/// A cheat goes from somewhere reachable from the start, straight through walls, to somewhere
+/// the end can be reached from, so the time it takes is the distance from the start to where it
+/// begins, plus the cheat itself, plus the distance from where it ends to the end.
+fn cheats(map: &Maze, start: Point, end: Point, limit: isize) -> Vec<Distance> {
+    let open = |p| p != Legend::Wall;
+    let from_start = map.distances(&[start], open);
+    let to_end = map.distances(&[end], open);
+    let Some(fair) = from_start.read_at(end).flatten() else {
+        // No way through without cheating
+        return Vec::new();
+    };
 
-fn cheats(map: &Maze) -> Vec<Distance> {
     let mut v: Vec<Distance> = Vec::new();
-    for wall in map.find(|p| p == Legend::Wall) {
-        let left = map.read(wall.0 - 1, wall.1).unwrap_or_default();
-        let right = map.read(wall.0 + 1, wall.1).unwrap_or_default();
-        let up = map.read(wall.0, wall.1 - 1).unwrap_or_default();
-        let down = map.read(wall.0, wall.1 + 1).unwrap_or_default();
-        if let Legend::Route(a) = left {
-            if let Legend::Route(b) = right {
-                skip(a, b, &mut v);
-            }
-            if let Legend::Route(b) = up {
-                skip(a, b, &mut v);
-            }
-            if let Legend::Route(b) = down {
-                skip(a, b, &mut v);
-            }
-        }
-        if let Legend::Route(a) = right {
-            if let Legend::Route(b) = up {
-                skip(a, b, &mut v);
-            }
-            if let Legend::Route(b) = down {
-                skip(a, b, &mut v);
-            }
-        }
-        if let Legend::Route(a) = up {
-            if let Legend::Route(b) = down {
-                skip(a, b, &mut v);
+    for (from, before) in from_start.cells() {
+        let Some(before) = before else {
+            continue;
+        };
+        for dy in -limit..=limit {
+            let w = limit - dy.abs();
+            for dx in -w..=w {
+                let to = from + Point::new(dx, dy);
+                let Some(Some(after)) = to_end.read_at(to) else {
+                    continue;
+                };
+                let time = before + from.manhattan(to) as Distance + after;
+                if time < fair {
+                    v.push(fair - time);
+                }
             }
         }
     }
     v
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut map: Maze = ctxt.value().parse().unwrap();
-    obvious(&mut map);
-    let options = cheats(&map);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Maze = ctxt.map(ctxt.value())?;
+    let (start, end) = ends(&ctxt, &map)?;
+    let options = cheats(&map, start, end, 2);
     let count = options.into_iter().filter(|&d| d >= 100).count();
-    println!("{count} cheats would save at least 100 picoseconds");
+    Ok(count.into())
 }
 
-fn longer_skip(a: Distance, b: Distance, d: Distance) -> Option<Distance> {
-    if a > b + d {
-        Some(a - b - d)
-    } else {
-        None
-    }
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let map: Maze = ctxt.map(ctxt.value())?;
+    let (start, end) = ends(&ctxt, &map)?;
+    let options = cheats(&map, start, end, 20);
+    let count = options.into_iter().filter(|&d| d >= 100).count();
+    Ok(count.into())
 }
 
-fn longer_cheats(map: &Maze) -> Vec<Distance> {
-    let mut v: Vec<Distance> = Vec::new();
-    for from in map.find(|p| matches!(p, Legend::Route(_))) {
-        let fx = from.0;
-        let fy = from.1;
-        let from = map.read(fx, fy).unwrap_or_default();
-        let Legend::Route(a) = from else {
-            panic!("How did we end up not on the route?");
-        };
-        for x in (fx - 20)..=(fx + 20) {
-            let w = fx.abs_diff(x) as isize;
-            for y in (fy + w - 20)..=(fy + 20 - w) {
-                let to = map.read(x, y).unwrap_or_default();
-                if let Legend::Route(b) = to {
-                    if let Some(distance) =
-                        longer_skip(b, a, (fx.abs_diff(x) + fy.abs_diff(y)) as Distance)
-                    {
-                        v.push(distance);
-                    }
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(limit: isize) -> Vec<Distance> {
+        let ctxt = readfile("test-data/20example").unwrap();
+        let map = ctxt.map(ctxt.value()).unwrap();
+        let (start, end) = ends(&ctxt, &map).unwrap();
+        cheats(&map, start, end, limit)
     }
-    v
-}
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
-    let mut map: Maze = ctxt.value().parse().unwrap();
-    obvious(&mut map);
-    let options = longer_cheats(&map);
-    let count = options.into_iter().filter(|&d| d >= 100).count();
-    println!("{count} of the (optionally longer) cheats would save at least 100 picoseconds");
+    #[test]
+    fn example_a() {
+        let options = example(2);
+        assert_eq!(options.len(), 44);
+        assert_eq!(options.iter().filter(|&&d| d >= 20).count(), 5);
+        assert_eq!(options.iter().filter(|&&d| d >= 64).count(), 1);
+    }
+
+    #[test]
+    fn example_b() {
+        let options = example(20);
+        assert_eq!(options.iter().filter(|&&d| d >= 50).count(), 285);
+        assert_eq!(options.iter().filter(|&&d| d >= 76).count(), 3);
+    }
+
+    #[test]
+    fn open_maze() {
+        // Shortcuts through the walls between the bends of the corridor
+        let map: Maze = "#######\n#S#...#\n#.#.#.#\n#...#E#\n#######\n"
+            .parse()
+            .unwrap();
+        let (start, end) = (Point::new(1, 1), Point::new(5, 3));
+        let mut options = cheats(&map, start, end, 2);
+        options.sort_unstable();
+        assert_eq!(options, vec![2, 2, 4, 4]);
+    }
 }
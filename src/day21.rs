@@ -1,87 +1,84 @@
-use history::readfile;
+use history::memo::Memo;
+use history::{readfile, Answer, InputError, Mistake};
 
 type Num = i32;
 
-use std::collections::HashMap;
+/// Shortest presses to type each pattern on the directional keypad at each level
+type Presses = Memo<(u8, &'static str), usize>;
 
-#[derive(Clone, Debug)]
-struct Starship {
-    depth: u8,
-    cache: HashMap<(u8, &'static str), usize>,
-}
-
-impl Starship {
-    fn new(depth: u8) -> Starship {
-        Starship {
-            depth,
-            cache: HashMap::new(),
-        }
-    }
+fn arrow(memo: &mut Presses, level: u8, prev: char, this: char) -> usize {
+    let patterns = match (prev, this) {
+        ('A', 'A') | ('<', '<') | ('>', '>') | ('^', '^') | ('v', 'v') => ["A"].as_slice(),
+        ('A', '^') => ["<A"].as_slice(),
+        ('A', '>') => ["vA"].as_slice(),
+        ('A', 'v') => ["v<A", "<vA"].as_slice(),
+        ('A', '<') => ["v<<A"].as_slice(),
 
-    fn arrow(&mut self, level: u8, prev: char, this: char) -> usize {
-        let patterns = match (prev, this) {
-            ('A', 'A') | ('<', '<') | ('>', '>') | ('^', '^') | ('v', 'v') => ["A"].as_slice(),
-            ('A', '^') => ["<A"].as_slice(),
-            ('A', '>') => ["vA"].as_slice(),
-            ('A', 'v') => ["v<A", "<vA"].as_slice(),
-            ('A', '<') => ["v<<A"].as_slice(),
+        ('<', 'A') => [">>^A"].as_slice(),
+        ('<', '^') => [">^A"].as_slice(),
+        ('<', 'v') => [">A"].as_slice(),
 
-            ('<', 'A') => [">>^A"].as_slice(),
-            ('<', '^') => [">^A"].as_slice(),
-            ('<', 'v') => [">A"].as_slice(),
+        ('>', 'A') => ["^A"].as_slice(),
+        ('>', '^') => ["<^A", "^<A"].as_slice(),
+        ('>', 'v') => ["<A"].as_slice(),
 
-            ('>', 'A') => ["^A"].as_slice(),
-            ('>', '^') => ["<^A", "^<A"].as_slice(),
-            ('>', 'v') => ["<A"].as_slice(),
+        ('^', 'A') => [">A"].as_slice(),
+        ('^', '<') => ["v<A"].as_slice(),
+        ('^', '>') => ["v>A", ">vA"].as_slice(),
 
-            ('^', 'A') => [">A"].as_slice(),
-            ('^', '<') => ["v<A"].as_slice(),
-            ('^', '>') => ["v>A", ">vA"].as_slice(),
+        ('v', 'A') => ["^>A", ">^A"].as_slice(),
+        ('v', '<') => ["<A"].as_slice(),
+        ('v', '>') => [">A"].as_slice(),
 
-            ('v', 'A') => ["^>A", ">^A"].as_slice(),
-            ('v', '<') => ["<A"].as_slice(),
-            ('v', '>') => [">A"].as_slice(),
-
-            ('<', '>') | ('>', '<') | ('^', 'v') | ('v', '^') => {
-                panic!("Contradictory movements '{prev}' and '{this}'")
-            }
-            _ => panic!("Can't handle arrow '{prev}' to '{this}' yet"),
-        };
-        let mut shortest: Option<usize> = None;
-        for pattern in patterns {
-            let length = self.shortest(level - 1, pattern);
-            if let Some(s) = shortest {
-                if s > length {
-                    shortest = Some(length);
-                }
-            } else {
+        ('<', '>') | ('>', '<') | ('^', 'v') | ('v', '^') => {
+            panic!("Contradictory movements '{prev}' and '{this}'")
+        }
+        _ => panic!("Can't handle arrow '{prev}' to '{this}' yet"),
+    };
+    let mut shortest: Option<usize> = None;
+    for pattern in patterns {
+        let length = directional(memo, level - 1, pattern);
+        if let Some(s) = shortest {
+            if s > length {
                 shortest = Some(length);
             }
+        } else {
+            shortest = Some(length);
         }
-        shortest.unwrap()
     }
+    shortest.unwrap()
+}
 
-    fn shortest(&mut self, level: u8, pattern: &'static str) -> usize {
-        debug_assert!(pattern.ends_with('A'));
-        // 1. Are we level 0? Use length of pattern
-        if level == 0 {
-            return pattern.len();
-        }
-
-        if let Some(&value) = self.cache.get(&(level, pattern)) {
-            return value;
-        }
+fn directional(memo: &mut Presses, level: u8, pattern: &'static str) -> usize {
+    debug_assert!(pattern.ends_with('A'));
+    // 1. Are we level 0? Use length of pattern
+    if level == 0 {
+        return pattern.len();
+    }
 
-        // Calculate length
+    memo.get_or((level, pattern), |memo| {
         let mut length = 0;
         let mut prev = 'A';
         for ch in pattern.chars() {
-            length += self.arrow(level, prev, ch);
+            length += arrow(memo, level, prev, ch);
             prev = ch;
         }
-        // Write to cache
-        self.cache.insert((level, pattern), length);
         length
+    })
+}
+
+#[derive(Clone, Debug)]
+struct Starship {
+    depth: u8,
+    presses: Presses,
+}
+
+impl Starship {
+    fn new(depth: u8) -> Starship {
+        Starship {
+            depth,
+            presses: Memo::new(),
+        }
     }
 
     const fn rowcol(d: char) -> (i8, i8) {
@@ -156,7 +153,7 @@ impl Starship {
 
         let mut shortest: Option<usize> = None;
         for pattern in patterns {
-            let length = self.shortest(self.depth, pattern);
+            let length = directional(&mut self.presses, self.depth, pattern);
             if let Some(s) = shortest {
                 if s > length {
                     shortest = Some(length);
@@ -181,6 +178,21 @@ impl Starship {
     }
 }
 
+/// Door codes are digits on the numeric keypad, finishing with A
+fn code(line: &str) -> Result<&str, Mistake<'_>> {
+    if let Some(n) = line.find(|c: char| !c.is_ascii_digit() && c != 'A') {
+        let bad = line[n..].chars().next().expect("find gave us a character");
+        return Err(Mistake::new(
+            &line[n..n + bad.len_utf8()],
+            "Codes should only use keys from the numeric keypad",
+        ));
+    }
+    if !line.ends_with('A') {
+        return Err(Mistake::new(line, "Codes should finish with A"));
+    }
+    Ok(line)
+}
+
 fn numeric(s: &str) -> Num {
     let front = s
         .split(|c: char| !c.is_ascii_digit())
@@ -195,22 +207,44 @@ fn numeric(s: &str) -> Num {
     }
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut ship = Starship::new(2);
-    let sum: usize = ctxt
-        .lines()
-        .map(|line| numeric(line) as usize * ship.number(line))
-        .sum();
-    println!("The sum of complexities of the five codes is: {sum}");
+    let mut sum: usize = 0;
+    for line in ctxt.lines() {
+        let line = ctxt.locate(code(line))?;
+        sum += numeric(line) as usize * ship.number(line);
+    }
+    Ok(sum.into())
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut ship = Starship::new(25);
-    let sum: usize = ctxt
-        .lines()
-        .map(|line| numeric(line) as usize * ship.number(line))
-        .sum();
-    println!("The new sum of complexities of the five codes is: {sum}");
+    let mut sum: usize = 0;
+    for line in ctxt.lines() {
+        let line = ctxt.locate(code(line))?;
+        sum += numeric(line) as usize * ship.number(line);
+    }
+    Ok(sum.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/21example"), Ok(Answer::Number(126384)));
+    }
+
+    // No answer is published for 25 robot keypads, this is the output of the current
+    // code, so it only notices if that changes
+    #[test]
+    fn regression_b() {
+        assert_eq!(
+            b("test-data/21example"),
+            Ok(Answer::Number(154115708116294))
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{readfile, Answer, InputError};
 
 type Num = u64;
 
@@ -27,15 +27,15 @@ fn future(mut secret: Num, generations: Num) -> Num {
     secret
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut sum = 0;
     for line in ctxt.lines() {
-        let secret: Num = line.parse().expect("Should be a number");
+        let secret: Num = ctxt.parse(line, "Secrets should be numbers")?;
         let becomes = future(secret, 2000);
         sum += becomes;
     }
-    println!("The sum of the 2000th secret numbers generated by each buyer was: {sum}");
+    Ok(sum.into())
 }
 
 fn price(secret: Num) -> i8 {
@@ -88,11 +88,11 @@ fn summarise_into(mut secret: Num, summary: &mut Summary) {
     }
 }
 
-pub fn b(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut summary = HashMap::with_capacity(2000);
     for line in ctxt.lines() {
-        let secret: Num = line.parse().expect("Should be a number");
+        let secret: Num = ctxt.parse(line, "Secrets should be numbers")?;
         summarise_into(secret, &mut summary);
     }
     let mut most: Option<Num> = None;
@@ -105,11 +105,8 @@ pub fn b(filename: &str) {
             most = Some(bananas);
         }
     }
-    if let Some(bananas) = most {
-        println!("We can get at most {bananas} bananas");
-    } else {
-        println!("No bananas available");
-    }
+    let most = most.ok_or_else(|| ctxt.missing("some bananas should be available"))?;
+    Ok(most.into())
 }
 
 #[cfg(test)]
@@ -160,4 +157,14 @@ mod tests {
         assert_eq!(future(secret, 9), 7753432);
         assert_eq!(future(secret, 10), 5908254);
     }
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/22example"), Ok(Answer::Number(37327623)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/22bananas"), Ok(Answer::Number(23)));
+    }
 }
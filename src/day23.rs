@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{readfile, Answer, InputError, Mistake};
 
 type Id = u16;
 
@@ -18,12 +18,17 @@ impl Network {
         }
     }
 
-    fn name_to_id(&mut self, name: &str) -> Id {
-        debug_assert!(name.len() == 2);
+    fn name_to_id<'t>(&mut self, name: &'t str) -> Result<Id, Mistake<'t>> {
         let bytes = name.as_bytes();
+        if bytes.len() != 2 || !bytes.iter().all(u8::is_ascii_lowercase) {
+            return Err(Mistake::new(
+                name,
+                "Computer names should be two lowercase letters",
+            ));
+        }
         let id = bytes[0] as Id * 256 + bytes[1] as Id;
         self.names.insert(id);
-        id
+        Ok(id)
     }
 
     fn id_to_name(&self, id: Id) -> String {
@@ -62,23 +67,25 @@ impl Network {
         self.links.entry(b).or_default().push(a);
     }
 
-    fn parse(filename: &str) -> Self {
+    fn parse(filename: &str) -> Result<Self, InputError> {
         let mut net = Self::new();
-        let ctxt = readfile(filename);
+        let ctxt = readfile(filename)?;
         for line in ctxt.lines() {
-            let (a, b) = line
-                .split_once('-')
-                .expect("Each line should be in the form ab-cd");
-            let a = net.name_to_id(a);
-            let b = net.name_to_id(b);
+            let (a, b) = line.split_once('-').ok_or_else(|| {
+                ctxt.error(Mistake::new(line, "Each line should be in the form ab-cd"))
+            })?;
+            let a = ctxt.locate(net.name_to_id(a))?;
+            let b = ctxt.locate(net.name_to_id(b))?;
             net.connect(a, b);
         }
-        net
+        Ok(net)
     }
 
     fn party(&self) -> Vec<Id> {
         let mut biggest = Vec::new();
         let mut remainder: Vec<Id> = self.names.iter().copied().collect();
+        // HashSet order changes from run to run, which would change which party is found
+        remainder.sort_unstable();
         while !remainder.is_empty() {
             let mut next: Vec<Id> = Vec::with_capacity(remainder.len());
             let mut big: Vec<Id> = Vec::new();
@@ -102,8 +109,8 @@ impl Network {
     }
 }
 
-pub fn a(filename: &str) {
-    let net = Network::parse(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let net = Network::parse(filename)?;
     let mut count = 0;
     let threes = net.threes();
     for (a, b, c) in threes {
@@ -114,16 +121,29 @@ pub fn a(filename: &str) {
             count += 1;
         }
     }
-    println!(
-        "{count} sets of three interconnected computers have at least one named starting with t"
-    );
+    Ok(count.into())
 }
 
-pub fn b(filename: &str) {
-    let net = Network::parse(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let net = Network::parse(filename)?;
     let party = net.party();
     let mut party: Vec<_> = party.into_iter().map(|i| net.id_to_name(i)).collect();
     party.sort_unstable();
     let password: String = party.join(",");
-    println!("The password is: {password}");
+    Ok(password.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/23example"), Ok(Answer::Number(7)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(b("test-data/23example"), Ok(Answer::from("co,de,ka,ta")));
+    }
 }
@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{readfile, Answer, Contents, InputError, Mistake};
 use std::collections::HashMap;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -12,6 +12,13 @@ impl Id {
         Self(id)
     }
 
+    fn parse(name: &str) -> Result<Self, Mistake<'_>> {
+        if name.len() != 3 || !name.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(Mistake::new(name, "Wire names should be three characters"));
+        }
+        Ok(Self::name_to_id(name))
+    }
+
     fn id_to_name(self) -> String {
         debug_assert!(self.0 > 0x10101 && self.0 < 0x7f7f7f);
         let mut name = String::with_capacity(2);
@@ -60,38 +67,24 @@ impl Gate {
         false
     }
 
-    fn parse(s: &str, out: Id) -> Option<Self> {
-        if let Some((left, right)) = s.split_once(" AND ") {
-            let left = Id::name_to_id(left);
-            let right = Id::name_to_id(right);
-            return Some(Gate {
-                left,
-                right,
-                out,
-                kind: Kind::And,
-            });
-        }
-        if let Some((left, right)) = s.split_once(" OR ") {
-            let left = Id::name_to_id(left);
-            let right = Id::name_to_id(right);
-            return Some(Gate {
-                left,
-                right,
-                out,
-                kind: Kind::Or,
-            });
-        }
-        if let Some((left, right)) = s.split_once(" XOR ") {
-            let left = Id::name_to_id(left);
-            let right = Id::name_to_id(right);
-            return Some(Gate {
-                left,
-                right,
-                out,
-                kind: Kind::Xor,
-            });
+    fn parse(s: &str, out: Id) -> Result<Self, Mistake<'_>> {
+        for (op, kind) in [
+            (" AND ", Kind::And),
+            (" OR ", Kind::Or),
+            (" XOR ", Kind::Xor),
+        ] {
+            if let Some((left, right)) = s.split_once(op) {
+                let left = Id::parse(left)?;
+                let right = Id::parse(right)?;
+                return Ok(Gate {
+                    left,
+                    right,
+                    out,
+                    kind,
+                });
+            }
         }
-        None
+        Err(Mistake::new(s, "Gates should be AND, OR or XOR"))
     }
 
     fn has_inputs(&self, a: Id, b: Id) -> bool {
@@ -221,45 +214,51 @@ impl Device {
         }
     }
 
-    fn parse(filename: &str) -> Self {
-        let ctxt = readfile(filename);
+    fn parse(ctxt: &Contents) -> Result<Self, InputError> {
         let mut dev = Self::new();
         let mut lines = ctxt.lines();
         for line in lines.by_ref() {
             if line.is_empty() {
                 break;
             }
-            let (wire, init) = line.split_once(": ").expect("Wires should go wr5: 0");
-            let wire = Id::name_to_id(wire);
+            let (wire, init) = line
+                .split_once(": ")
+                .ok_or_else(|| ctxt.error(Mistake::new(line, "Wires should go wr5: 0")))?;
+            let wire = ctxt.locate(Id::parse(wire))?;
             let init = match init {
                 "0" => false,
                 "1" => true,
-                _ => panic!("Initial wire level should not be {init}"),
+                _ => {
+                    return Err(
+                        ctxt.error(Mistake::new(init, "Initial wire level should be 0 or 1"))
+                    )
+                }
             };
             dev.set_wire(wire, init);
         }
         for line in lines {
-            let (gate, output) = line.split_once(" -> ").expect("Gates should have output");
-            let output = Id::name_to_id(output);
-            if let Some(gate) = Gate::parse(gate, output) {
-                dev.gates.push(gate);
-            } else {
-                panic!("Unexpected gate: {line}");
-            }
+            let (gate, output) = line
+                .split_once(" -> ")
+                .ok_or_else(|| ctxt.error(Mistake::new(line, "Gates should have output")))?;
+            let output = ctxt.locate(Id::parse(output))?;
+            let gate = ctxt.locate(Gate::parse(gate, output))?;
+            dev.gates.push(gate);
         }
-        dev
+        Ok(dev)
     }
 }
 
-pub fn a(filename: &str) {
-    let mut dev = Device::parse(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut dev = Device::parse(&ctxt)?;
     dev.settle();
     let z = dev.number("z");
-    println!("The decimal number on wires starting z was: {z}");
+    Ok(z.into())
 }
 
-pub fn b(filename: &str) {
-    let mut dev = Device::parse(filename);
+pub fn b(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
+    let mut dev = Device::parse(&ctxt)?;
 
     let mut swaps: Vec<String> = Vec::new();
     while let Err((a, b)) = dev.check() {
@@ -272,16 +271,15 @@ pub fn b(filename: &str) {
     let y = dev.number("y");
     dev.settle();
     let diff = (x + y) ^ dev.number("z");
-    if diff == 0 {
-        swaps.sort_unstable();
-        let answer: String = swaps.join(",");
-        println!("Eight wires to swap are: {answer}");
-    } else {
-        println!(
+    if diff != 0 {
+        return Err(ctxt.overall(format!(
             "Swaps chosen didn't work for test input, {} differences\n{diff:050b}",
             diff.count_ones()
-        );
+        )));
     }
+    swaps.sort_unstable();
+    let answer: String = swaps.join(",");
+    Ok(answer.into())
 }
 
 #[cfg(test)]
@@ -300,4 +298,9 @@ mod tests {
         assert!(Id::name_to_id("z01").prefix(b'z'));
         assert!(Id::name_to_id("x18").prefix(b'x'));
     }
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/24example"), Ok(Answer::Number(4)));
+    }
 }
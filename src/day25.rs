@@ -1,4 +1,4 @@
-use history::readfile;
+use history::{readfile, Answer, Contents, InputError, Mistake};
 
 type Pins = [u8; 5];
 
@@ -8,39 +8,47 @@ enum Schematic {
     Lock(Pins),
 }
 
-fn parse<'t, T>(lines: &mut T) -> Schematic
+fn parse<'t, T>(ctxt: &Contents, lines: &mut T) -> Result<Schematic, InputError>
 where
     T: Iterator<Item = &'t str>,
 {
     let Some(first) = lines.next() else {
-        panic!("Should be a schematic");
+        return Err(ctxt.missing("Should be a schematic"));
     };
     let mut array: [u8; 5] = [0, 0, 0, 0, 0];
     let initial = match first {
         "#####" => '#',
         "....." => '.',
         _ => {
-            panic!("Schematic should show a key ..... or a lock  ##### not {first}");
+            return Err(ctxt.error(Mistake::new(
+                first,
+                "Schematic should show a key ..... or a lock #####",
+            )));
         }
     };
     for depth in 1..=5 {
         let Some(pins) = lines.next() else {
-            panic!("Schematics should have seven lines in total");
+            return Err(ctxt.missing("Schematics should have seven lines in total"));
         };
+        if pins.len() != 5 {
+            return Err(ctxt.error(Mistake::new(pins, "Schematics should be five pins wide")));
+        }
         for (n, pin) in pins.chars().enumerate() {
             if pin == initial {
                 array[n] = depth;
             }
         }
     }
-    assert!(lines.next().is_some());
+    if lines.next().is_none() {
+        return Err(ctxt.missing("Schematics should have seven lines in total"));
+    }
     match initial {
-        '#' => Schematic::Lock(array),
+        '#' => Ok(Schematic::Lock(array)),
         '.' => {
             for pin in array.iter_mut() {
                 *pin = 5 - *pin;
             }
-            Schematic::Key(array)
+            Ok(Schematic::Key(array))
         }
         _ => unreachable!("Should be either a lock or a key"),
     }
@@ -50,15 +58,15 @@ fn fit(key: &Pins, lock: &Pins) -> bool {
     lock.iter().zip(key).all(|(lock, key)| lock + key < 6)
 }
 
-pub fn a(filename: &str) {
-    let ctxt = readfile(filename);
+pub fn a(filename: &str) -> Result<Answer, InputError> {
+    let ctxt = readfile(filename)?;
     let mut lines = ctxt.lines();
 
     let mut locks: Vec<Pins> = Vec::new();
     let mut keys: Vec<Pins> = Vec::new();
 
     loop {
-        match parse(&mut lines) {
+        match parse(&ctxt, &mut lines)? {
             Schematic::Lock(lock) => {
                 locks.push(lock);
             }
@@ -78,9 +86,27 @@ pub fn a(filename: &str) {
             }
         }
     }
-    println!("{count} keys fit");
+    Ok(count.into())
+}
+
+pub fn b(_filename: &str) -> Result<Answer, InputError> {
+    Ok("Happy Christmas!".into())
 }
 
-pub fn b(_filename: &str) {
-    println!("Happy Christmas!");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_a() {
+        assert_eq!(a("test-data/25example"), Ok(Answer::Number(3)));
+    }
+
+    #[test]
+    fn example_b() {
+        assert_eq!(
+            b("test-data/25example"),
+            Ok(Answer::from("Happy Christmas!"))
+        );
+    }
 }
@@ -0,0 +1,229 @@
+use crate::map::Map;
+use std::io::{self, Write};
+
+/// How each cell of a Map should look in a picture, like Legend::to_char but for images
+pub trait Colour: Copy {
+    /// Red, green and blue
+    fn to_rgb(self) -> [u8; 3];
+}
+
+/// Set cells are white and unset cells are black
+impl Colour for bool {
+    fn to_rgb(self) -> [u8; 3] {
+        if self {
+            [255, 255, 255]
+        } else {
+            [0, 0, 0]
+        }
+    }
+}
+
+/// Export a Map as a picture with one pixel for each cell within the x() and y() ranges
+/// Use Map::scale first for bigger pixels
+impl<T: Colour + Default> Map<T> {
+    fn width(&self) -> usize {
+        self.x().count()
+    }
+
+    fn height(&self) -> usize {
+        self.y().count()
+    }
+
+    /// Binary PPM (P6), which almost any image tool can read
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        for row in self.rows() {
+            for cell in row.iter() {
+                out.write_all(&cell.to_rgb())?;
+            }
+        }
+        out.flush()
+    }
+
+    /// PNG, without compression since we only need something a viewer will open
+    pub fn write_png<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width() as u32).to_be_bytes());
+        header.extend((self.height() as u32).to_be_bytes());
+        // 8 bits per channel, RGB, standard compression, filtering and no interlace
+        header.extend([8, 2, 0, 0, 0]);
+        chunk(&mut out, b"IHDR", &header)?;
+
+        // Each scanline is the "None" filter type followed by its pixels
+        let mut pixels = Vec::with_capacity((1 + 3 * self.width()) * self.height());
+        for row in self.rows() {
+            pixels.push(0);
+            for cell in row.iter() {
+                pixels.extend(cell.to_rgb());
+            }
+        }
+        chunk(&mut out, b"IDAT", &zlib(&pixels))?;
+
+        chunk(&mut out, b"IEND", &[])?;
+        out.flush()
+    }
+}
+
+/// A PNG chunk is its length, type, data and a CRC of the type and data
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// CRC-32 as used by PNG (and zip, and ethernet)
+fn crc32<'b>(bytes: impl Iterator<Item = &'b u8>) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// A zlib stream holding data in stored (uncompressed) deflate blocks
+fn zlib(data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 65535;
+    let mut v = Vec::with_capacity(data.len() + 6 + 5 * (data.len() / BLOCK + 1));
+    v.extend([0x78, 0x01]);
+    let mut blocks = data.chunks(BLOCK).peekable();
+    if blocks.peek().is_none() {
+        v.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        v.push(last as u8);
+        v.extend(len.to_le_bytes());
+        v.extend((!len).to_le_bytes());
+        v.extend(block);
+    }
+    v.extend(adler32(data).to_be_bytes());
+    v
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &byte in data {
+        a = (a + byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripes() -> Map<bool> {
+        let mut map = Map::ranged(0..=2, 0..=1);
+        map.write(0, 0, true);
+        map.write(2, 0, true);
+        map.write(1, 1, true);
+        map
+    }
+
+    #[test]
+    fn ppm() {
+        let mut out = Vec::new();
+        stripes().write_ppm(&mut out).unwrap();
+        let (header, pixels) = out.split_at(11);
+        assert_eq!(header, b"P6\n3 2\n255\n");
+        let white: Vec<bool> = pixels.chunks(3).map(|p| p == [255; 3]).collect();
+        assert_eq!(white, [true, false, true, false, true, false]);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND".iter()), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789".iter()), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn png() {
+        let mut out = Vec::new();
+        stripes().write_png(&mut out).unwrap();
+        assert_eq!(&out[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&out[12..16], b"IHDR");
+        assert_eq!(&out[16..24], [0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&out[out.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        // The pixels are stored as is, after the zlib and deflate block headers
+        let idat = 8 + 25;
+        assert_eq!(&out[idat + 4..idat + 8], b"IDAT");
+        let scanline = &out[idat + 8 + 7..idat + 8 + 7 + 10];
+        assert_eq!(scanline, [0, 255, 255, 255, 0, 0, 0, 255, 255, 255]);
+    }
+
+    /// The chunks of a PNG as (type, data), checking each CRC on the way
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut v = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+            let data = &rest[8..8 + len];
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc32(rest[4..8 + len].iter()), crc, "CRC of {kind:?}");
+            v.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        v
+    }
+
+    /// The data in a zlib stream of stored blocks, checking its Adler-32 on the way
+    fn unstore(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(stream[..2], [0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut rest = &stream[2..];
+        loop {
+            let last = rest[0] == 1;
+            let len = u16::from_le_bytes([rest[1], rest[2]]);
+            let nlen = u16::from_le_bytes([rest[3], rest[4]]);
+            assert_eq!(len, !nlen);
+            data.extend(&rest[5..5 + len as usize]);
+            rest = &rest[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(rest, adler32(&data).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn png_round_trip() {
+        // Big enough to need more than one stored block
+        let mut map: Map<bool> = Map::ranged(0..=199, 0..=119);
+        map.write(0, 0, false);
+        map.write(199, 119, true);
+        map.write(3, 0, true);
+        let mut out = Vec::new();
+        map.write_png(&mut out).unwrap();
+
+        let chunks = chunks(&out);
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        let pixels = unstore(chunks[1].1);
+        assert_eq!(pixels.len(), (1 + 3 * 200) * 120);
+        let mut white = Vec::new();
+        for (y, row) in pixels.chunks(1 + 3 * 200).enumerate() {
+            assert_eq!(row[0], 0);
+            for (x, pixel) in row[1..].chunks(3).enumerate() {
+                if pixel == [255; 3] {
+                    white.push((x, y));
+                }
+            }
+        }
+        assert_eq!(white, [(3, 0), (199, 119)]);
+    }
+}
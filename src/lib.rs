@@ -1,10 +1,7 @@
 #[macro_export]
 macro_rules! days {
-    ($obj:expr, $filename:expr, $($day:ident,)+) => {
-        days!($obj, $(days!($day a) => $day::a($filename), days!($day b) => $day::b($filename)),*, _ => { println!("{} not available yet", $obj); });
-    };
-    ($obj:expr, $filename:expr, $($day:ident),+) => {
-        days!($obj, $filename, $($day,)+);
+    (table $($day:ident),+ $(,)?) => {
+        [$((days!($day a), $day::a as $crate::Solver), (days!($day b), $day::b as $crate::Solver)),+]
     };
     ($s:ident a) => {
         concat!(stringify!($s), 'a')
@@ -12,14 +9,99 @@ macro_rules! days {
     ($s:ident b) => {
         concat!(stringify!($s), 'b')
     };
-    ($obj:expr, $($matcher:pat => $result:expr),*) => {
-       match $obj {
-           $($matcher => $result),*
-       }
+}
+
+/// The answer to one part of a day's puzzle, most are numbers but a few are text
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+/// Each part of each day is solved by a function with this signature
+pub type Solver = fn(&str) -> Result<Answer, InputError>;
+
+macro_rules! numeric_answer {
+    ($($t:ty),+) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Answer::Number(n as i128)
+            }
+        })+
+    };
+}
+
+numeric_answer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, i128, isize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_owned())
+    }
+}
+
+impl std::fmt::Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{n}"),
+            Answer::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Something wrong with the puzzle input, saying where and what the offending text was
+/// line and column count from 1, but are 0 if the problem wasn't within the text itself
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub problem: String,
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.file, self.problem)
+        } else {
+            write!(
+                f,
+                "{}:{}:{}: {}: {:?}",
+                self.file, self.line, self.column, self.problem, self.text
+            )
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// A problem while parsing, text should be the offending slice of the input so that
+/// Contents::error can work out where it was
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mistake<'t> {
+    pub text: &'t str,
+    pub problem: &'static str,
+}
+
+impl<'t> Mistake<'t> {
+    pub fn new(text: &'t str, problem: &'static str) -> Self {
+        Self { text, problem }
     }
 }
 
+/// Parse text as a T, or explain the problem with a Mistake
+pub fn parse<'t, T: FromStr>(text: &'t str, problem: &'static str) -> Result<T, Mistake<'t>> {
+    text.parse().map_err(|_| Mistake::new(text, problem))
+}
+
 pub struct Contents {
+    pub filename: String,
     pub text: String,
 }
 
@@ -28,8 +110,9 @@ impl<'t> Contents {
         self.text.lines()
     }
 
-    pub fn numbers(&self) -> impl Iterator<Item = isize> + use<'_> {
-        self.lines().map(|l| l.parse::<isize>().unwrap_or(0))
+    pub fn numbers(&self) -> impl Iterator<Item = Result<isize, InputError>> + use<'_> {
+        self.lines()
+            .map(|l| self.parse(l, "Each line should be a number"))
     }
 
     pub fn binary(&self) -> impl Iterator<Item = isize> + use<'_> {
@@ -37,43 +120,201 @@ impl<'t> Contents {
             .filter_map(|l| isize::from_str_radix(l, 2).ok())
     }
 
-    pub fn digits(&self) -> impl Iterator<Item = u32> + use<'_> {
-        self.text.trim().chars().map(|c| c.to_digit(10).unwrap())
+    pub fn digits(&self) -> impl Iterator<Item = Result<u32, InputError>> + use<'_> {
+        let value = self.value();
+        value.char_indices().map(|(n, c)| {
+            c.to_digit(10).ok_or_else(|| {
+                let digit = &value[n..n + c.len_utf8()];
+                self.error(Mistake::new(digit, "Should be a digit"))
+            })
+        })
     }
 
     pub fn list(&'t self) -> impl Iterator<Item = &'t str> {
         self.text.trim().split(',')
     }
 
-    pub fn list_numbers(&self) -> impl Iterator<Item = isize> + use<'_> {
-        self.list().map(|n| n.parse().unwrap())
+    pub fn list_numbers(&self) -> impl Iterator<Item = Result<isize, InputError>> + use<'_> {
+        self.list()
+            .map(|n| self.parse(n, "Each item in the list should be a number"))
     }
 
     pub fn value(&self) -> &str {
         self.text.trim()
     }
 
-    pub fn number(&self) -> isize {
-        self.value().parse().unwrap()
+    pub fn number(&self) -> Result<isize, InputError> {
+        self.parse(self.value(), "Should be a number")
+    }
+
+    /// InputError explaining a Mistake, which is found by where its text lies in our text
+    pub fn error(&self, mistake: Mistake<'_>) -> InputError {
+        let base = self.text.as_ptr() as usize;
+        let at = mistake.text.as_ptr() as usize;
+        let (line, column) = if at >= base && at <= base + self.text.len() {
+            let before = &self.text[..(at - base)];
+            let line = before.matches('\n').count() + 1;
+            let start = before.rfind('\n').map_or(0, |n| n + 1);
+            (line, before[start..].chars().count() + 1)
+        } else {
+            (0, 0)
+        };
+        InputError {
+            file: self.filename.clone(),
+            line,
+            column,
+            text: mistake.text.to_owned(),
+            problem: mistake.problem.to_owned(),
+        }
+    }
+
+    /// Turn any Mistake in the result into an InputError
+    pub fn locate<T>(&self, result: Result<T, Mistake<'_>>) -> Result<T, InputError> {
+        result.map_err(|mistake| self.error(mistake))
+    }
+
+    /// Parse some of our text as a T, or explain the problem with an InputError
+    pub fn parse<T: FromStr>(&self, text: &str, problem: &'static str) -> Result<T, InputError> {
+        self.locate(parse(text, problem))
+    }
+
+    /// Parse some of our text as a Map, or explain which cell didn't fit the Legend
+    pub fn map<T: Legend + Default>(&self, text: &str) -> Result<Map<T>, InputError> {
+        text.parse().map_err(|bad: BadCell| {
+            let line = text
+                .lines()
+                .nth(bad.y as usize)
+                .expect("BadCell should be in text");
+            let (n, ch) = line
+                .char_indices()
+                .nth(bad.x as usize)
+                .expect("BadCell should be in text");
+            let cell = &line[n..n + ch.len_utf8()];
+            InputError {
+                problem: bad.to_string(),
+                ..self.error(Mistake::new(cell, "Unexpected symbol on map"))
+            }
+        })
+    }
+
+    /// InputError for a problem with our text as a whole, not at any line or column of it,
+    /// such as a puzzle which can't be solved for this input
+    pub fn overall(&self, problem: String) -> InputError {
+        InputError {
+            file: self.filename.clone(),
+            line: 0,
+            column: 0,
+            text: String::new(),
+            problem,
+        }
+    }
+
+    /// InputError for something missing at the end of our text
+    pub fn missing(&self, problem: &'static str) -> InputError {
+        self.error(Mistake::new(&self.text[self.text.len()..], problem))
     }
 }
 
+use map::{BadCell, Legend, Map};
 use std::fs;
 
-pub fn readfile(filename: &str) -> Contents {
+pub fn readfile(filename: &str) -> Result<Contents, InputError> {
     match fs::read_to_string(filename) {
-        Ok(text) => Contents { text },
-        Err(e) => {
-            panic!("When attempting to read \"{filename}\" - {e}");
-        }
+        Ok(text) => Ok(Contents {
+            filename: filename.to_owned(),
+            text,
+        }),
+        Err(e) => Err(InputError {
+            file: filename.to_owned(),
+            line: 0,
+            column: 0,
+            text: String::new(),
+            problem: e.to_string(),
+        }),
     }
 }
 
 use core::ops::ControlFlow;
+use std::cmp::Reverse;
 use std::collections::hash_map;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::hash::Hash;
 
+/// Known-good answers, one per line as day, part and value e.g. "17 a 4,6,3,5,6,3,5,2,1,0"
+/// Blank lines and lines beginning with # are ignored
+#[derive(Clone, Debug, Default)]
+pub struct Expected {
+    answers: HashMap<(u8, char), String>,
+}
+
+/// Outcome of checking an Answer against what was Expected
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Verdict::Pass => "PASS",
+            Verdict::Fail => "FAIL",
+            Verdict::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+use std::str::FromStr;
+
+impl Expected {
+    pub fn parse(s: &str) -> Result<Self, Mistake<'_>> {
+        let mut answers = HashMap::new();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (day, rest) = line.split_once(char::is_whitespace).ok_or(Mistake::new(
+                line,
+                "Expected answers should give day, part and value",
+            ))?;
+            let (part, value) =
+                rest.trim_start()
+                    .split_once(char::is_whitespace)
+                    .ok_or(Mistake::new(
+                        line,
+                        "Expected answers should give day, part and value",
+                    ))?;
+            let day: u8 = parse(day, "Day should be a number")?;
+            let part = match part {
+                "a" | "A" => 'a',
+                "b" | "B" => 'b',
+                _ => return Err(Mistake::new(part, "Part should be a or b")),
+            };
+            answers.insert((day, part), value.trim().to_owned());
+        }
+        Ok(Expected { answers })
+    }
+
+    /// Load expected answers from a file, if there is no such file nothing is known
+    pub fn load(filename: &str) -> Result<Self, InputError> {
+        if !std::path::Path::new(filename).exists() {
+            return Ok(Self::default());
+        }
+        let ctxt = readfile(filename)?;
+        ctxt.locate(Self::parse(&ctxt.text))
+    }
+
+    pub fn check(&self, day: u8, part: char, answer: &Answer) -> Verdict {
+        match self.answers.get(&(day, part)) {
+            None => Verdict::Unknown,
+            Some(value) if *value == answer.to_string() => Verdict::Pass,
+            Some(_) => Verdict::Fail,
+        }
+    }
+}
+
 /// T is a type for an invariant, such as a map
 pub trait State<T>: Copy + Eq + Hash {
     fn describe(&self, invariant: &T) -> String;
@@ -164,6 +405,135 @@ pub trait State<T>: Copy + Eq + Hash {
         Self::steps(initial, |&s| s == goal, invariant)
     }
 
+    /// Successors, each with the cost of moving there
+    /// by default every successor from next costs 1, so this is just like the other searches
+    fn weighted(&self, invariant: &T) -> Vec<(Self, usize)> {
+        self.next(invariant).into_iter().map(|s| (s, 1)).collect()
+    }
+
+    /// Lowest total cost (of weighted transitions) from initial until predicate is true,
+    /// or None if that's not possible
+    fn cheapest<P>(initial: Self, predicate: P, invariant: &T) -> Option<usize>
+    where
+        P: Fn(&Self) -> bool,
+    {
+        Self::cheapest_path(initial, predicate, invariant).map(|(cost, _)| cost)
+    }
+
+    /// Like cheapest, but also the States along the way, from initial to the one which satisfied
+    /// the predicate, using Dijkstra's algorithm
+    fn cheapest_path<P>(initial: Self, predicate: P, invariant: &T) -> Option<(usize, Vec<Self>)>
+    where
+        P: Fn(&Self) -> bool,
+    {
+        Self::astar(initial, predicate, |_| 0, invariant)
+    }
+
+    /// Lowest total cost and the States along the way, like cheapest_path, but exploring first
+    /// the States which the heuristic estimates are closest to satisfying the predicate
+    /// The heuristic must never overestimate the remaining cost (e.g. Manhattan distance on a grid
+    /// where each step costs 1) otherwise the answer may not be the cheapest
+    fn astar<P, H>(
+        initial: Self,
+        predicate: P,
+        heuristic: H,
+        invariant: &T,
+    ) -> Option<(usize, Vec<Self>)>
+    where
+        P: Fn(&Self) -> bool,
+        H: Fn(&Self) -> usize,
+    {
+        // Each state we reach is kept with the index of the state we came from
+        let mut states: Vec<(Self, usize)> = vec![(initial, 0)];
+        let mut lowest: HashMap<Self, usize> = HashMap::new();
+        lowest.insert(initial, 0);
+        // Ordered by estimated total, then by actual cost so far
+        let mut frontier: BinaryHeap<Reverse<(usize, usize, usize)>> = BinaryHeap::new();
+        frontier.push(Reverse((heuristic(&initial), 0, 0)));
+
+        while let Some(Reverse((_, cost, index))) = frontier.pop() {
+            let (state, _) = states[index];
+            if lowest.get(&state).is_some_and(|&c| c < cost) {
+                // Already reached more cheaply
+                continue;
+            }
+            if predicate(&state) {
+                let mut path = vec![state];
+                let mut index = index;
+                while index != 0 {
+                    index = states[index].1;
+                    path.push(states[index].0);
+                }
+                path.reverse();
+                return Some((cost, path));
+            }
+            for (new, step) in state.weighted(invariant) {
+                let total = cost + step;
+                if lowest.get(&new).is_none_or(|&c| c > total) {
+                    lowest.insert(new, total);
+                    states.push((new, index));
+                    frontier.push(Reverse((total + heuristic(&new), total, states.len() - 1)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Every optimal route (of weighted transitions) from initial to any State for which the
+    /// predicate is true, or None if that's not possible
+    /// Only the predecessors of each State are recorded, see Optimal for what can be found
+    fn optimal<P>(initial: Self, predicate: P, invariant: &T) -> Option<Optimal<Self>>
+    where
+        P: Fn(&Self) -> bool,
+    {
+        let mut states: Vec<Self> = vec![initial];
+        let mut costs: HashMap<Self, usize> = HashMap::new();
+        costs.insert(initial, 0);
+        let mut preds: HashMap<Self, Vec<Self>> = HashMap::new();
+        let mut frontier: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+        frontier.push(Reverse((0, 0)));
+        let mut goals: Vec<Self> = Vec::new();
+        let mut best: Option<usize> = None;
+
+        while let Some(Reverse((cost, index))) = frontier.pop() {
+            if best.is_some_and(|b| cost > b) {
+                break;
+            }
+            let state = states[index];
+            if costs.get(&state).is_some_and(|&c| c < cost) {
+                continue;
+            }
+            if predicate(&state) {
+                best = Some(cost);
+                goals.push(state);
+                continue;
+            }
+            for (new, step) in state.weighted(invariant) {
+                let total = cost + step;
+                match costs.get(&new) {
+                    Some(&c) if c < total => (),
+                    Some(&c) if c == total => {
+                        preds.entry(new).or_default().push(state);
+                    }
+                    _ => {
+                        costs.insert(new, total);
+                        preds.insert(new, vec![state]);
+                        states.push(new);
+                        frontier.push(Reverse((total, states.len() - 1)));
+                    }
+                }
+            }
+        }
+
+        Some(Optimal {
+            cost: best?,
+            initial,
+            goals,
+            costs,
+            preds,
+        })
+    }
+
     /// Possible states after up to steps taken
     fn count(initial: Self, steps: usize, invariant: &T) -> usize {
         let mut seen: HashSet<Self> = HashSet::new();
@@ -187,21 +557,20 @@ pub trait State<T>: Copy + Eq + Hash {
     }
 
     /// Report (if debug is true) the States from the goal back to the initial
-    fn report(initial: Self, goal: Self, invariant: &T, debug: bool) -> Self {
+    /// None if the goal can't be reached, use route to get the States themselves
+    fn report(initial: Self, goal: Self, invariant: &T, debug: bool) -> Option<Self> {
         if initial == goal {
-            return initial;
+            return Some(initial);
         }
 
         let mut seen: HashMap<Self, Self> = HashMap::new();
         seen.insert(initial, initial);
-        let mut current: Vec<Self> = Vec::with_capacity(1);
-        current.push(initial);
+        let mut current: Vec<Self> = vec![initial];
 
-        loop {
+        while !current.is_empty() {
             let mut next: Vec<Self> = Vec::new();
             for state in current {
-                let more = state.next(invariant);
-                for new in more {
+                for new in state.next(invariant) {
                     if let hash_map::Entry::Vacant(e) = seen.entry(new) {
                         e.insert(state);
                         next.push(new);
@@ -211,10 +580,10 @@ pub trait State<T>: Copy + Eq + Hash {
                             let mut prev = &new;
                             while prev != &initial {
                                 println!("D: {}", prev.describe(invariant));
-                                prev = seen.get(prev).unwrap();
+                                prev = &seen[prev];
                             }
                         }
-                        return new;
+                        return Some(new);
                     }
                 }
             }
@@ -224,6 +593,134 @@ pub trait State<T>: Copy + Eq + Hash {
             }
             current = next;
         }
+        None
+    }
+
+    /// Fewest State transitions from initial to goal, as each State in order from initial to
+    /// goal inclusive, or None if the goal can't be reached
+    fn route(initial: Self, goal: Self, invariant: &T) -> Option<Vec<Self>> {
+        let mut seen: HashMap<Self, Self> = HashMap::new();
+        seen.insert(initial, initial);
+        let mut current: Vec<Self> = vec![initial];
+
+        while !seen.contains_key(&goal) {
+            if current.is_empty() {
+                return None;
+            }
+            let mut next: Vec<Self> = Vec::new();
+            for state in current {
+                for new in state.next(invariant) {
+                    if let hash_map::Entry::Vacant(e) = seen.entry(new) {
+                        e.insert(state);
+                        next.push(new);
+                    }
+                }
+            }
+            current = next;
+        }
+
+        let mut route = vec![goal];
+        let mut prev = goal;
+        while prev != initial {
+            prev = seen[&prev];
+            route.push(prev);
+        }
+        route.reverse();
+        Some(route)
+    }
+}
+
+/// All the optimal routes found by State::optimal, recorded as the predecessors of each State
+/// rather than as a path for each route
+#[derive(Clone, Debug)]
+pub struct Optimal<S> {
+    /// Cost of every one of the optimal routes
+    pub cost: usize,
+    initial: S,
+    goals: Vec<S>,
+    costs: HashMap<S, usize>,
+    preds: HashMap<S, Vec<S>>,
+}
+
+impl<S: Copy + Eq + Hash> Optimal<S> {
+    /// States which satisfied the predicate at the optimal cost
+    pub fn goals(&self) -> &[S] {
+        &self.goals
+    }
+
+    /// Every State which is on at least one optimal route, including initial and goals
+    pub fn states(&self) -> HashSet<S> {
+        let mut seen: HashSet<S> = self.goals.iter().copied().collect();
+        let mut todo: Vec<S> = self.goals.clone();
+        while let Some(state) = todo.pop() {
+            for &pred in self.preds.get(&state).into_iter().flatten() {
+                if seen.insert(pred) {
+                    todo.push(pred);
+                }
+            }
+        }
+        seen
+    }
+
+    /// How many distinct optimal routes there are
+    pub fn count(&self) -> usize {
+        let mut states: Vec<S> = self.states().into_iter().collect();
+        states.sort_by_key(|s| self.costs[s]);
+        // Every predecessor is cheaper so it's counted before we need it
+        let mut routes: HashMap<S, usize> = HashMap::with_capacity(states.len());
+        for state in states {
+            let n = if state == self.initial {
+                1
+            } else {
+                self.preds[&state].iter().map(|p| routes[p]).sum()
+            };
+            routes.insert(state, n);
+        }
+        self.goals.iter().map(|g| routes[g]).sum()
+    }
+
+    /// Each optimal route in turn, from initial to a goal
+    pub fn paths(&self) -> Paths<'_, S> {
+        Paths {
+            optimal: self,
+            goal: 0,
+            stack: Vec::new(),
+        }
+    }
+}
+
+/// Iterator over the optimal routes, working back through the predecessors
+pub struct Paths<'o, S> {
+    optimal: &'o Optimal<S>,
+    goal: usize,
+    // Each State back from the goal, with which of its predecessors to try next
+    stack: Vec<(S, usize)>,
+}
+
+impl<S: Copy + Eq + Hash> Iterator for Paths<'_, S> {
+    type Item = Vec<S>;
+
+    fn next(&mut self) -> Option<Vec<S>> {
+        loop {
+            let Some(&mut (state, ref mut tried)) = self.stack.last_mut() else {
+                let goal = *self.optimal.goals.get(self.goal)?;
+                self.goal += 1;
+                self.stack.push((goal, 0));
+                continue;
+            };
+            if state == self.optimal.initial {
+                let path: Vec<S> = self.stack.iter().rev().map(|&(s, _)| s).collect();
+                self.stack.pop();
+                return Some(path);
+            }
+            let preds = &self.optimal.preds[&state];
+            if let Some(&pred) = preds.get(*tried) {
+                *tried += 1;
+                self.stack.push((pred, 0));
+            } else {
+                self.stack.pop();
+            }
+        }
     }
 }
 
@@ -248,6 +745,7 @@ pub const fn permutations(n: usize) -> usize {
 
 /// Heap's Algorithm for permuting slices up to length 10
 /// handle zero specially to do nothing
+#[allow(clippy::manual_is_multiple_of)]
 pub fn heap<T>(a: &mut [T], n: usize) {
     assert!(a.len() < 11);
     if n == 0 {
@@ -277,12 +775,208 @@ pub fn heap<T>(a: &mut [T], n: usize) {
     }
 }
 
+pub mod animate;
+pub mod cycle;
+pub mod image;
 pub mod map;
+pub mod memo;
 
 #[cfg(test)]
-
 mod tests {
-    use crate::heap;
+    use crate::{heap, Answer, Contents, Expected, Mistake, State, Verdict};
+    use std::collections::HashSet;
+
+    #[test]
+    fn answers() {
+        assert_eq!(Answer::from(42_u64), Answer::Number(42));
+        assert_eq!(Answer::from(-7_isize), Answer::Number(-7));
+        assert_eq!(Answer::from("4,6,3"), Answer::Text("4,6,3".to_owned()));
+        assert_eq!(Answer::from(123_usize).to_string(), "123");
+        assert_eq!(Answer::from("co,de,ka,ta").to_string(), "co,de,ka,ta");
+    }
+
+    #[test]
+    fn expected() {
+        let expected = Expected::parse("# Comment\n1 a 11\n\n17 b 4,6,3\n").unwrap();
+        assert_eq!(expected.check(1, 'a', &Answer::Number(11)), Verdict::Pass);
+        assert_eq!(expected.check(1, 'a', &Answer::Number(12)), Verdict::Fail);
+        assert_eq!(
+            expected.check(1, 'b', &Answer::Number(31)),
+            Verdict::Unknown
+        );
+        assert_eq!(expected.check(17, 'b', &"4,6,3".into()), Verdict::Pass);
+        assert_eq!(
+            Expected::parse("1 c 11").unwrap_err(),
+            Mistake::new("c", "Part should be a or b")
+        );
+        assert!(Expected::parse("1a").is_err());
+    }
+
+    #[test]
+    fn locate() {
+        let ctxt = Contents {
+            filename: "example".to_owned(),
+            text: "1 a 11\n2 b ✓ 4x\n".to_owned(),
+        };
+        let Err(e) = ctxt.parse::<u8>(&ctxt.text[15..17], "Should be a number") else {
+            panic!("4x is not a number");
+        };
+        assert_eq!((e.line, e.column), (2, 7));
+        assert_eq!(e.text, "4x");
+        assert_eq!(e.to_string(), "example:2:7: Should be a number: \"4x\"");
+        let e = ctxt.missing("Should be more");
+        assert_eq!((e.line, e.column), (3, 1));
+        let e = ctxt.error(Mistake::new("elsewhere", "Not in the text"));
+        assert_eq!((e.line, e.column), (0, 0));
+        let e = ctxt.overall("No answer fits".to_owned());
+        assert_eq!(e.to_string(), "example: No answer fits");
+    }
+
+    #[test]
+    fn bad_numbers() {
+        let ctxt = Contents {
+            filename: "example".to_owned(),
+            text: "12\n3x\n".to_owned(),
+        };
+        let numbers: Vec<_> = ctxt.numbers().collect();
+        assert_eq!(numbers[0], Ok(12));
+        let e = numbers[1].clone().unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "3x"));
+        assert!(ctxt.number().is_err());
+        let ctxt = Contents {
+            text: "1234x6\n".to_owned(),
+            ..ctxt
+        };
+        let e = ctxt.digits().find_map(Result::err).unwrap();
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 5, "x"));
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    struct Stop(u8);
+
+    /// Stops along a line, walking to the next stop costs 5 but the bus from stop 0 straight
+    /// to stop 4 costs 12
+    impl State<()> for Stop {
+        fn describe(&self, _: &()) -> String {
+            format!("stop {}", self.0)
+        }
+
+        fn next(&self, invariant: &()) -> Vec<Self> {
+            self.weighted(invariant)
+                .into_iter()
+                .map(|(s, _)| s)
+                .collect()
+        }
+
+        fn weighted(&self, _: &()) -> Vec<(Self, usize)> {
+            let mut v = vec![(Stop(self.0 + 1), 5)];
+            if self.0 == 0 {
+                v.push((Stop(4), 12));
+            }
+            v
+        }
+    }
+
+    #[test]
+    fn cheapest() {
+        assert_eq!(Stop::best(Stop(0), Stop(5), &()), Some(2));
+        assert_eq!(State::cheapest(Stop(0), |s| s.0 == 3, &()), Some(15));
+        assert_eq!(
+            State::cheapest_path(Stop(0), |s| s.0 == 5, &()),
+            Some((17, vec![Stop(0), Stop(4), Stop(5)]))
+        );
+        assert_eq!(
+            State::cheapest_path(Stop(2), |s| s.0 == 2, &()),
+            Some((0, vec![Stop(2)]))
+        );
+    }
+
+    #[test]
+    fn astar() {
+        // Never overestimates, since it's at least 3 per stop on average
+        let guess = |s: &Stop| 3 * 7_usize.saturating_sub(s.0 as usize);
+        assert_eq!(
+            State::astar(Stop(0), |s| s.0 == 7, guess, &()),
+            Some((27, vec![Stop(0), Stop(4), Stop(5), Stop(6), Stop(7)]))
+        );
+        assert_eq!(
+            State::astar(Stop(5), |s| s.0 == 7, guess, &()),
+            Some((10, vec![Stop(5), Stop(6), Stop(7)]))
+        );
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    struct Corner(u8, u8);
+
+    /// Walk right or down across a 3x3 grid
+    impl State<()> for Corner {
+        fn describe(&self, _: &()) -> String {
+            format!("{},{}", self.0, self.1)
+        }
+
+        fn next(&self, _: &()) -> Vec<Self> {
+            let mut v = Vec::new();
+            if self.0 < 2 {
+                v.push(Corner(self.0 + 1, self.1));
+            }
+            if self.1 < 2 {
+                v.push(Corner(self.0, self.1 + 1));
+            }
+            v
+        }
+    }
+
+    #[test]
+    fn route() {
+        let route = State::route(Stop(0), Stop(5), &()).unwrap();
+        assert_eq!(route, vec![Stop(0), Stop(4), Stop(5)]);
+        let route = State::route(Corner(0, 0), Corner(2, 2), &()).unwrap();
+        assert_eq!(route.len(), 5);
+        assert_eq!(route.first(), Some(&Corner(0, 0)));
+        assert_eq!(route.last(), Some(&Corner(2, 2)));
+        assert_eq!(
+            State::route(Corner(1, 1), Corner(1, 1), &()),
+            Some(vec![Corner(1, 1)])
+        );
+        assert_eq!(State::route(Corner(1, 1), Corner(0, 0), &()), None);
+    }
+
+    #[test]
+    fn report() {
+        assert_eq!(
+            State::report(Corner(0, 0), Corner(2, 2), &(), false),
+            Some(Corner(2, 2))
+        );
+        assert_eq!(State::report(Corner(2, 1), Corner(0, 0), &(), false), None);
+    }
+
+    #[test]
+    fn optimal() {
+        let optimal = State::optimal(Corner(0, 0), |&c| c == Corner(2, 2), &()).unwrap();
+        assert_eq!(optimal.cost, 4);
+        assert_eq!(optimal.goals(), &[Corner(2, 2)]);
+        assert_eq!(optimal.count(), 6);
+        assert_eq!(optimal.states().len(), 9);
+        let paths: HashSet<Vec<Corner>> = optimal.paths().collect();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.contains(&vec![
+            Corner(0, 0),
+            Corner(1, 0),
+            Corner(1, 1),
+            Corner(2, 1),
+            Corner(2, 2)
+        ]));
+
+        let optimal = State::optimal(Corner(1, 1), |&c| c.0 == 2, &()).unwrap();
+        assert_eq!(optimal.cost, 1);
+        assert_eq!(optimal.goals(), &[Corner(2, 1)]);
+        let optimal = State::optimal(Corner(1, 1), |&c| c.0 == 1, &()).unwrap();
+        assert_eq!(
+            optimal.paths().collect::<Vec<_>>(),
+            vec![vec![Corner(1, 1)]]
+        );
+        assert!(State::optimal(Corner(1, 1), |&c| c.0 == 0, &()).is_none());
+    }
 
     #[test]
     fn heap_two() {
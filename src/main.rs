@@ -24,52 +24,234 @@ mod day23;
 mod day24;
 mod day25;
 
-use history::days;
+use history::animate::{self, Pace};
+use history::{days, Expected, Solver, Verdict};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Name (e.g. "day04b") and solver for each part of each day
+type Part = (&'static str, Solver);
+
+/// Which days and parts were asked for: "all", a day "12", a part "12b",
+/// or a range of days "3..=9" or "3..10"
+fn choose(wanted: &str) -> Option<(RangeInclusive<u8>, Option<char>)> {
+    let wanted = wanted.to_ascii_lowercase();
+    if wanted == "all" {
+        return Some((1..=25, None));
+    }
+    if let Some((first, last)) = wanted.split_once("..=") {
+        return Some((first.parse().ok()?..=last.parse().ok()?, None));
+    }
+    if let Some((first, last)) = wanted.split_once("..") {
+        let last: u8 = last.parse().ok()?;
+        return Some((first.parse().ok()?..=last.checked_sub(1)?, None));
+    }
+    let day = wanted.trim_end_matches(char::is_alphabetic);
+    let part = match &wanted[day.len()..] {
+        "" => None,
+        "a" => Some('a'),
+        "b" => Some('b'),
+        _ => return None,
+    };
+    let day: u8 = day.parse().ok()?;
+    Some((day..=day, part))
+}
+
+/// How to show frames for --visualise (100ms apart), --visualise=250 (250ms apart) or
+/// --visualise=step (one frame each time Enter is pressed)
+fn pace(flag: &str) -> Option<Pace> {
+    match flag.strip_prefix("--visualise")? {
+        "" => Some(Pace::Delay(animate::DELAY)),
+        "=step" => Some(Pace::Step),
+        ms => {
+            let ms: u64 = ms.strip_prefix('=')?.parse().ok()?;
+            Some(Pace::Delay(Duration::from_millis(ms)))
+        }
+    }
+}
+
+/// Day number and part letter from a name like "day04b"
+fn day_part(name: &str) -> (u8, char) {
+    let day = name[3..5].parse().expect("names should be dayNNx");
+    let part = name[5..].chars().next().expect("names should be dayNNx");
+    (day, part)
+}
+
+fn input(day: u8, extra: Option<&str>) -> String {
+    if let Some(extra) = extra {
+        format!("test-data/{day:0>2}{extra}")
+    } else {
+        format!("{day:0>2}")
+    }
+}
+
+/// Expected answers are kept next to the inputs, one file for each suffix
+fn answers(extra: Option<&str>) -> String {
+    if let Some(extra) = extra {
+        format!("test-data/answers{extra}")
+    } else {
+        "answers".to_owned()
+    }
+}
+
+/// Run each of the parts, reporting answer, verdict and wall-clock time for each, then totals
+/// Returns how many parts went wrong, either with an answer which did not match the one expected
+/// or stopping with an error instead of an answer
+fn run_all(parts: &[Part], extra: Option<&str>, expected: &Expected) -> usize {
+    println!("Day Part {:>12}  {:<7}  Answer", "Time", "Check");
+    let mut total = Duration::ZERO;
+    let mut ran = 0;
+    let mut missing = 0;
+    let mut failed = 0;
+    let mut errors = 0;
+    for &(name, solver) in parts {
+        let (day, part) = day_part(name);
+        let file = input(day, extra);
+        if !Path::new(&file).exists() {
+            println!(
+                "{day:>3} {part:>4} {:>12}  {:<7}  (no input {file})",
+                "-", "-"
+            );
+            missing += 1;
+            continue;
+        }
+        let start = Instant::now();
+        let answer = solver(&file);
+        let elapsed = start.elapsed();
+        total += elapsed;
+        ran += 1;
+        let answer = match answer {
+            Ok(answer) => answer,
+            Err(e) => {
+                println!(
+                    "{day:>3} {part:>4} {:>12}  {:<7}  {e}",
+                    format!("{elapsed:.3?}"),
+                    "ERROR"
+                );
+                errors += 1;
+                continue;
+            }
+        };
+        let verdict = expected.check(day, part, &answer);
+        if verdict == Verdict::Fail {
+            failed += 1;
+        }
+        println!(
+            "{day:>3} {part:>4} {:>12}  {:<7}  {answer}",
+            format!("{elapsed:.3?}"),
+            verdict.to_string()
+        );
+    }
+    println!("Ran {ran} parts in {total:.3?}");
+    if missing > 0 {
+        println!("Skipped {missing} parts without input");
+    }
+    if errors > 0 {
+        println!("{errors} parts stopped with an error");
+    }
+    if failed > 0 {
+        println!("{failed} answers did not match those expected");
+    }
+    failed + errors
+}
 
 fn main() {
-    let mut args = std::env::args();
-    args.next();
+    let solvers = days!(
+        table day01,
+        day02,
+        day03,
+        day04,
+        day05,
+        day06,
+        day07,
+        day08,
+        day09,
+        day10,
+        day11,
+        day12,
+        day13,
+        day14,
+        day15,
+        day16,
+        day17,
+        day18,
+        day19,
+        day20,
+        day21,
+        day22,
+        day23,
+        day24,
+        day25,
+    );
 
-    if let Some(day) = args.next() {
-        let fun = format!("day{:0>3}", day.to_ascii_lowercase());
-        let day = day.trim_end_matches(char::is_alphabetic);
-        let file = if let Some(extra) = args.next() {
-            format!("test-data/{:0>2}{extra}", day)
-        } else {
-            format!("{:0>2}", day)
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    for flag in flags {
+        let Some(pace) = pace(&flag) else {
+            eprintln!("Did not understand the option \"{flag}\"");
+            std::process::exit(2);
         };
+        animate::start(pace);
+    }
+    let mut args = args.into_iter();
 
-        days!(
-            fun.as_str(),
-            file.as_str(),
-            day01,
-            day02,
-            day03,
-            day04,
-            day05,
-            day06,
-            day07,
-            day08,
-            day09,
-            day10,
-            day11,
-            day12,
-            day13,
-            day14,
-            day15,
-            day16,
-            day17,
-            day18,
-            day19,
-            day20,
-            day21,
-            day22,
-            day23,
-            day24,
-            day25,
-        );
+    if let Some(wanted) = args.next() {
+        let extra = args.next();
+        let Some((days, part)) = choose(&wanted) else {
+            eprintln!("Did not understand which day(s) \"{wanted}\" means");
+            std::process::exit(2);
+        };
+        let parts: Vec<Part> = solvers
+            .into_iter()
+            .filter(|&(name, _)| {
+                let (d, p) = day_part(name);
+                days.contains(&d) && part.is_none_or(|part| part == p)
+            })
+            .collect();
+        let expected = match Expected::load(&answers(extra.as_deref())) {
+            Ok(expected) => expected,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        };
+        let failed = match parts.as_slice() {
+            [] => {
+                println!("{wanted} not available yet");
+                0
+            }
+            [(name, solver)] => {
+                let (day, part) = day_part(name);
+                let answer = match solver(&input(day, extra.as_deref())) {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        std::process::exit(1);
+                    }
+                };
+                let verdict = expected.check(day, part, &answer);
+                if verdict == Verdict::Unknown {
+                    println!("{answer}");
+                } else {
+                    println!("{answer} {verdict}");
+                }
+                usize::from(verdict == Verdict::Fail)
+            }
+            _ => run_all(&parts, extra.as_deref(), &expected),
+        };
+        if failed > 0 {
+            std::process::exit(1);
+        }
     } else {
         eprintln!("Provide a parameter specifying which day e.g. 1a means day 1, part A while 4b means day 4, part B.");
+        eprintln!(
+            "Use all for every day, 12 for both parts of day 12, or a range of days such as 3..=9"
+        );
         eprintln!("You may also optionally specify a filename suffix e.g. 5b test will use the file 05test");
+        eprintln!("Answers are checked against the file answers (or e.g. test-data/answerstest) with lines like: 5 b 123");
+        eprintln!("Add --visualise to watch the days which can show their Map, --visualise=250 to go slower, or --visualise=step to press Enter for each frame");
+        eprintln!("While watching, day 14 part B also saves the tree it found as day14.png");
     }
 }
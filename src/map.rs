@@ -1,20 +1,194 @@
-use std::ops::RangeInclusive;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, AddAssign, BitAnd, BitOr, Mul, Neg, RangeInclusive, Sub, SubAssign};
 
-/// A Plane with size zero has no map cells inside it
+/// A position on a Map, x increases to the East and y increases to the South
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    /// Steps needed to get from here to there, moving only North, East, South or West
+    pub const fn manhattan(self, other: Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Point> for (isize, isize) {
+    fn from(p: Point) -> Self {
+        (p.x, p.y)
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Point {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Self;
+
+    fn mul(self, n: isize) -> Self {
+        Self::new(self.x * n, self.y * n)
+    }
+}
+
+/// One step in that direction
+impl Add<Direction> for Point {
+    type Output = Self;
+
+    fn add(self, dir: Direction) -> Self {
+        self + dir.offset()
+    }
+}
+
+impl AddAssign<Direction> for Point {
+    fn add_assign(&mut self, dir: Direction) {
+        *self = *self + dir;
+    }
+}
+
+/// Compass directions on a Map, North is up, towards smaller y
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise from North
+    pub const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// Offset of a single step in this direction
+    pub const fn offset(self) -> Point {
+        match self {
+            Self::North => Point::new(0, -1),
+            Self::East => Point::new(1, 0),
+            Self::South => Point::new(0, 1),
+            Self::West => Point::new(-1, 0),
+        }
+    }
+
+    /// Turn right 90°
+    pub const fn clockwise(self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+        }
+    }
+
+    /// Turn left 90°
+    pub const fn anticlockwise(self) -> Self {
+        match self {
+            Self::North => Self::West,
+            Self::East => Self::North,
+            Self::South => Self::East,
+            Self::West => Self::South,
+        }
+    }
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+
+    /// Either an arrow ^ > v < or a compass letter N E S W
+    pub const fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            '^' | 'N' => Some(Self::North),
+            '>' | 'E' => Some(Self::East),
+            'v' | 'S' => Some(Self::South),
+            '<' | 'W' => Some(Self::West),
+            _ => None,
+        }
+    }
+
+    /// The arrow pointing this way
+    pub const fn to_char(self) -> char {
+        match self {
+            Self::North => '^',
+            Self::East => '>',
+            Self::South => 'v',
+            Self::West => '<',
+        }
+    }
+}
+
+/// A Plane with size zero has no map cells inside it, and end is start - 1
 /// A Plane with size 1 has one map cell, start == end == offset
 #[derive(Copy, Clone, Debug, PartialEq)]
 struct Plane {
     size: isize,
     offset: isize,
     start: isize, // Inclusive, between offset and (size+offset-1) inclusive
-    end: isize,   // Inclusive, ditto, but also >= start
+    end: isize,   // Inclusive, ditto, but also >= start unless the Plane is empty
 }
 
 impl Plane {
     fn from_to(from: isize, to: isize) -> Self {
-        if to < from {
+        if to < from - 1 {
             panic!("{from} to {to} is not reasonable for defining a Plane");
         }
+        if to < from {
+            return Self {
+                size: 0,
+                offset: from,
+                start: from,
+                end: to,
+            };
+        }
         let width = to - from;
         let mid = from + (width / 2);
         Self {
@@ -31,8 +205,11 @@ impl Plane {
         const GROWTH: isize = 8;
         debug_assert!(self.end - self.start < self.size);
 
-        let start = if new < self.start { new } else { self.start };
-        let end = if new > self.end { new } else { self.end };
+        let (start, end) = if self.size == 0 {
+            (new, new)
+        } else {
+            (self.start.min(new), self.end.max(new))
+        };
 
         let offset = if self.offset < start - GROWTH {
             self.offset
@@ -55,13 +232,49 @@ impl Plane {
     fn inbound(&self, pos: isize) -> bool {
         pos >= self.offset && pos < (self.offset + self.size)
     }
+
+    // Exactly start..=end with no slack, since a sparse Map needn't allocate ahead
+    fn stretch(&self, new: isize) -> Self {
+        let start = self.start.min(new);
+        let end = self.end.max(new);
+        Self {
+            size: end - start + 1,
+            offset: start,
+            start,
+            end,
+        }
+    }
+}
+
+/// Offsets to the four cells which share an edge, clockwise from above
+pub const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+/// Offsets to the four cells which only share a corner, clockwise from top left
+pub const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+/// Offsets to all eight cells around, clockwise from top left
+pub const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+/// Where the cells of a Map live, either every cell in the planes, or only those written
+#[derive(Clone)]
+enum Store<T> {
+    Dense(Vec<T>),
+    Sparse(HashMap<(isize, isize), T>),
 }
 
 #[derive(Clone)]
 pub struct Map<T: Copy + Default> {
-    data: Vec<T>,
+    data: Store<T>,
     x: Plane,
     y: Plane,
+    torus: bool,
 }
 
 impl<T: Copy + Default> Default for Map<T> {
@@ -77,20 +290,25 @@ impl<T: Copy + Default> Map<T> {
     /// Map a Rectangle initially from (x1, y1) to (x2, y2) but it will grow automatically as
     /// necessary
     pub fn rect((x1, y1): (isize, isize), (x2, y2): (isize, isize)) -> Self {
-        if x2 < x1 {
-            panic!("{x1} must be less than or equal to {x2}");
+        if x2 < x1 - 1 {
+            panic!("{x1} must be less than or equal to {x2}, or one more for no columns");
         }
         let x = Plane::from_to(x1, x2);
 
-        if y2 < y1 {
-            panic!("{y1} must be less than or equal to {y2}");
+        if y2 < y1 - 1 {
+            panic!("{y1} must be less than or equal to {y2}, or one more for no rows");
         }
         let y = Plane::from_to(y1, y2);
 
         let size = (x.size * y.size) as usize;
         let mut data = Vec::with_capacity(size);
         data.resize_with(size, Default::default);
-        Self { data, x, y }
+        Self {
+            data: Store::Dense(data),
+            x,
+            y,
+            torus: false,
+        }
     }
 
     pub fn ranged(x: RangeInclusive<isize>, y: RangeInclusive<isize>) -> Self {
@@ -103,12 +321,60 @@ impl<T: Copy + Default> Map<T> {
         Self::rect((-8, -8), (8, 8))
     }
 
+    /// Like ranged, but x() and y() are those ranges from the outset, rather than growing as
+    /// cells are written
+    fn exactly(x: RangeInclusive<isize>, y: RangeInclusive<isize>) -> Self {
+        let mut map = Self::ranged(x.clone(), y.clone());
+        (map.x.start, map.x.end) = x.into_inner();
+        (map.y.start, map.y.end) = y.into_inner();
+        map
+    }
+
+    /// A Map which only stores the cells actually written, so writes far apart stay cheap
+    /// The x() and y() ranges are exactly those covering every write, empty until the first,
+    /// and reading anywhere in them which wasn't written gives the default. Unlike a rect Map,
+    /// find only reports written cells, since there may be vastly more default cells than memory.
+    pub fn sparse() -> Self {
+        Self {
+            data: Store::Sparse(HashMap::new()),
+            x: Plane::from_to(0, -1),
+            y: Plane::from_to(0, -1),
+            torus: false,
+        }
+    }
+
+    /// A Map of fixed size, width by height from (0, 0), where co-ordinates wrap around at the
+    /// edges, so (-1, 0) is (width - 1, 0) and (0, height) is (0, 0)
+    pub fn torus(width: isize, height: isize) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "A torus can't be {width} by {height}"
+        );
+        let mut map = Self::rect((0, 0), (width - 1, height - 1));
+        (map.x.start, map.x.end) = (0, width - 1);
+        (map.y.start, map.y.end) = (0, height - 1);
+        map.torus = true;
+        map
+    }
+
+    /// Where (x, y) really is, which is only somewhere else for a torus
+    fn wrap(&self, x: isize, y: isize) -> (isize, isize) {
+        if self.torus {
+            (x.rem_euclid(self.x.size), y.rem_euclid(self.y.size))
+        } else {
+            (x, y)
+        }
+    }
+
     fn inbound(&self, x: isize, y: isize) -> bool {
         self.x.inbound(x) && self.y.inbound(y)
     }
 
     /// Grow Map by suitably expanding both planes and re-allocating, then copying
     fn grow(&mut self, x: isize, y: isize) {
+        let Store::Dense(data) = &mut self.data else {
+            unreachable!("only a dense Map needs to grow");
+        };
         let new_x = self.x.expand(x);
         let new_y = self.y.expand(y);
 
@@ -126,17 +392,23 @@ impl<T: Copy + Default> Map<T> {
             for x in 0..right {
                 let from = y * self.x.size + x;
                 let dest = (y + off_y) * new_x.size + (x + off_x);
-                new_data[dest as usize] = self.data[from as usize];
+                new_data[dest as usize] = data[from as usize];
             }
         }
 
-        self.data = new_data;
+        *data = new_data;
         self.x = new_x;
         self.y = new_y;
     }
 
     fn include(&mut self, x: isize, y: isize) {
-        if self.inbound(x, y) {
+        if let Store::Sparse(_) = &self.data {
+            if self.x.size == 0 {
+                (self.x, self.y) = (Plane::from_to(x, x), Plane::from_to(y, y));
+            } else {
+                (self.x, self.y) = (self.x.stretch(x), self.y.stretch(y));
+            }
+        } else if self.inbound(x, y) {
             if x < self.x.start {
                 self.x.start = x;
             } else if x > self.x.end {
@@ -152,76 +424,1013 @@ impl<T: Copy + Default> Map<T> {
         }
     }
 
-    /// Range of X values, it is possible that this range includes some "dead" space
-    /// but if the Map was built by parsing a string, this will be the exact size
+    /// Range of X values, it is possible that this range includes some "dead" space
+    /// but if the Map was built by parsing a string, this will be the exact size
+    pub fn x(&self) -> RangeInclusive<isize> {
+        self.x.start..=self.x.end
+    }
+
+    /// Range of Y values, it is possible that this range includes some "dead" space
+    /// but if the Map was built by parsing a string, this will be the exact size
+    pub fn y(&self) -> RangeInclusive<isize> {
+        self.y.start..=self.y.end
+    }
+
+    fn noitisop(&self, i: usize) -> (isize, isize) {
+        let y = self.y.offset + (i as isize / self.x.size);
+        let x = self.x.offset + (i as isize % self.x.size);
+        (x, y)
+    }
+
+    fn position(&self, x: isize, y: isize) -> usize {
+        let posn = (y - self.y.offset) * self.x.size + (x - self.x.offset);
+        posn as usize
+    }
+
+    /// Write to (x, y) in the Map, this will grow the map automatically
+    pub fn write(&mut self, x: isize, y: isize, value: T) {
+        let (x, y) = self.wrap(x, y);
+        self.include(x, y);
+        // A sparse Map has no positions, its planes may be far too big to number the cells
+        if let Store::Sparse(cells) = &mut self.data {
+            cells.insert((x, y), value);
+            return;
+        }
+        let posn = self.position(x, y);
+        if let Store::Dense(data) = &mut self.data {
+            data[posn] = value;
+        }
+    }
+
+    /// Reads an (x, y) position on the Map, but can be None if that position wasn't yet mapped
+    /// Use or(value) or or_else(function) if appropriate
+    pub fn read(&self, x: isize, y: isize) -> Option<T> {
+        let (x, y) = self.wrap(x, y);
+        if !self.inbound(x, y) {
+            return None;
+        }
+        match &self.data {
+            Store::Dense(data) => Some(data[self.position(x, y)]),
+            Store::Sparse(cells) => Some(cells.get(&(x, y)).copied().unwrap_or_default()),
+        }
+    }
+
+    /// Each cell at one of the offsets from (x, y), with what read finds there
+    /// On a torus the positions are wrapped around too
+    pub fn neighbours<'m>(
+        &'m self,
+        x: isize,
+        y: isize,
+        offsets: &'m [(isize, isize)],
+    ) -> impl Iterator<Item = ((isize, isize), Option<T>)> + 'm {
+        offsets.iter().map(move |&(dx, dy)| {
+            let pos = self.wrap(x + dx, y + dy);
+            (pos, self.read(pos.0, pos.1))
+        })
+    }
+
+    /// Like neighbours, but skipping any cells outside the x() and y() ranges
+    pub fn bounded_neighbours<'m>(
+        &'m self,
+        x: isize,
+        y: isize,
+        offsets: &'m [(isize, isize)],
+    ) -> impl Iterator<Item = ((isize, isize), T)> + 'm {
+        let (wide, tall) = (self.x(), self.y());
+        self.neighbours(x, y, offsets)
+            .filter(move |&((x, y), _)| wide.contains(&x) && tall.contains(&y))
+            .map(|(pos, value)| (pos, value.expect("cells within bounds are mapped")))
+    }
+
+    /// The four neighbours sharing an edge with (x, y)
+    pub fn orthogonal(
+        &self,
+        x: isize,
+        y: isize,
+    ) -> impl Iterator<Item = ((isize, isize), Option<T>)> + '_ {
+        self.neighbours(x, y, &ORTHOGONAL)
+    }
+
+    /// The four neighbours only touching a corner of (x, y)
+    pub fn diagonal(
+        &self,
+        x: isize,
+        y: isize,
+    ) -> impl Iterator<Item = ((isize, isize), Option<T>)> + '_ {
+        self.neighbours(x, y, &DIAGONAL)
+    }
+
+    /// All eight neighbours around (x, y)
+    pub fn surrounding(
+        &self,
+        x: isize,
+        y: isize,
+    ) -> impl Iterator<Item = ((isize, isize), Option<T>)> + '_ {
+        self.neighbours(x, y, &SURROUNDING)
+    }
+
+    /// Like read, but at a Point
+    pub fn read_at(&self, p: Point) -> Option<T> {
+        self.read(p.x, p.y)
+    }
+
+    /// Like write, but at a Point
+    pub fn write_at(&mut self, p: Point, value: T) {
+        self.write(p.x, p.y, value);
+    }
+
+    /// Like find, but giving Points
+    pub fn find_points<P>(&self, predicate: P) -> Vec<Point>
+    where
+        P: Fn(T) -> bool,
+    {
+        self.find(predicate).into_iter().map(Point::from).collect()
+    }
+
+    /// Every cell connected to start (including start itself) through orthogonal neighbours within
+    /// the x() and y() ranges, where same is true for the value at start and the neighbour
+    pub fn flood<S>(&self, start: Point, same: S) -> Vec<Point>
+    where
+        S: Fn(T, T) -> bool,
+    {
+        let mut seen: Map<Option<usize>> = Map::ranged(self.x(), self.y());
+        self.fill(start, &same, &mut seen, 0)
+    }
+
+    fn fill<S>(
+        &self,
+        start: Point,
+        same: &S,
+        seen: &mut Map<Option<usize>>,
+        id: usize,
+    ) -> Vec<Point>
+    where
+        S: Fn(T, T) -> bool,
+    {
+        let Some(value) = self.read_at(start) else {
+            return Vec::new();
+        };
+        let mut cells = Vec::new();
+        let mut todo = vec![start];
+        seen.write_at(start, Some(id));
+        while let Some(p) = todo.pop() {
+            cells.push(p);
+            for (pos, other) in self.bounded_neighbours(p.x, p.y, &ORTHOGONAL) {
+                let pos = Point::from(pos);
+                if seen.read_at(pos).flatten().is_none() && same(value, other) {
+                    seen.write_at(pos, Some(id));
+                    todo.push(pos);
+                }
+            }
+        }
+        cells
+    }
+
+    /// Label each cell within the x() and y() ranges with the index of its connected Region,
+    /// neighbouring cells are in the same Region when same is true for their values
+    pub fn regions<S>(&self, same: S) -> (Map<Option<usize>>, Vec<Region>)
+    where
+        S: Fn(T, T) -> bool,
+    {
+        let mut labels: Map<Option<usize>> = Map::ranged(self.x(), self.y());
+        let mut regions = Vec::new();
+        for (start, _) in self.cells() {
+            if labels.read_at(start).flatten().is_some() {
+                continue;
+            }
+            let id = regions.len();
+            let cells = self.fill(start, &same, &mut labels, id);
+            regions.push(Region::measure(&cells, |p| {
+                labels.read_at(p).flatten() == Some(id)
+            }));
+        }
+        (labels, regions)
+    }
+
+    /// Fewest orthogonal steps within the x() and y() ranges from the nearest of the sources to
+    /// each cell, only stepping onto cells where passable is true. Cells which can't be reached
+    /// are None, and the resulting Map has the same x() and y() ranges as this one. Sources
+    /// outside those ranges are ignored.
+    pub fn distances<P>(&self, sources: &[Point], passable: P) -> Map<Option<u32>>
+    where
+        P: Fn(T) -> bool,
+    {
+        let (wide, tall) = (self.x(), self.y());
+        let mut distances: Map<Option<u32>> = Map::exactly(wide.clone(), tall.clone());
+        let mut todo = VecDeque::new();
+        for &p in sources {
+            let inside = wide.contains(&p.x) && tall.contains(&p.y);
+            if inside && distances.read_at(p).flatten().is_none() {
+                distances.write_at(p, Some(0));
+                todo.push_back((p, 0));
+            }
+        }
+        while let Some((p, d)) = todo.pop_front() {
+            for (pos, cell) in self.bounded_neighbours(p.x, p.y, &ORTHOGONAL) {
+                let pos = Point::from(pos);
+                if passable(cell) && distances.read_at(pos).flatten().is_none() {
+                    distances.write_at(pos, Some(d + 1));
+                    todo.push_back((pos, d + 1));
+                }
+            }
+        }
+        distances
+    }
+
+    /// Borrowed view of part of the Map, the ranges are clipped to x() and y()
+    pub fn window(&self, x: RangeInclusive<isize>, y: RangeInclusive<isize>) -> Window<'_, T> {
+        let (x1, x2) = x.into_inner();
+        let (y1, y2) = y.into_inner();
+        Window {
+            map: self,
+            x: x1.max(self.x.start)..=x2.min(self.x.end),
+            y: y1.max(self.y.start)..=y2.min(self.y.end),
+        }
+    }
+
+    /// View of the whole Map, everything within x() and y()
+    pub fn whole(&self) -> Window<'_, T> {
+        self.window(self.x(), self.y())
+    }
+
+    /// Each row within the x() and y() ranges, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = Cow<'_, [T]>> + '_ {
+        self.whole().rows()
+    }
+
+    /// Each column within the x() and y() ranges, left to right, each top to bottom
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = T> + '_> + '_ {
+        self.whole().columns()
+    }
+
+    /// Every cell within the x() and y() ranges with its position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (Point, T)> + '_ {
+        self.whole().cells()
+    }
+
+    /// Width and height of the x() and y() ranges
+    fn dimensions(&self) -> (isize, isize) {
+        (self.x.end - self.x.start + 1, self.y.end - self.y.start + 1)
+    }
+
+    /// New Map, wide by tall from the same top left, where each cell (i, j) counting from
+    /// that corner is copied from the cell from(i, j) in this Map
+    fn reshape<F>(&self, wide: isize, tall: isize, from: F) -> Self
+    where
+        F: Fn(isize, isize) -> (isize, isize),
+    {
+        let (left, top) = (self.x.start, self.y.start);
+        let mut map = Self::rect((left, top), (left + wide - 1, top + tall - 1));
+        for j in 0..tall {
+            for i in 0..wide {
+                let (x, y) = from(i, j);
+                let value = self.read(left + x, top + y).unwrap_or_default();
+                map.write(left + i, top + j, value);
+            }
+        }
+        map
+    }
+
+    /// Rotated clockwise by a number of quarter turns, keeping the top left corner in place
+    pub fn rotate(&self, turns: usize) -> Self {
+        let (w, h) = self.dimensions();
+        match turns % 4 {
+            0 => self.reshape(w, h, |i, j| (i, j)),
+            1 => self.reshape(h, w, |i, j| (j, h - 1 - i)),
+            2 => self.reshape(w, h, |i, j| (w - 1 - i, h - 1 - j)),
+            3 => self.reshape(h, w, |i, j| (w - 1 - j, i)),
+            _ => unreachable!("turns % 4 is less than 4"),
+        }
+    }
+
+    /// Mirrored so that left and right swap over
+    pub fn flip_horizontal(&self) -> Self {
+        let (w, h) = self.dimensions();
+        self.reshape(w, h, |i, j| (w - 1 - i, j))
+    }
+
+    /// Mirrored so that top and bottom swap over
+    pub fn flip_vertical(&self) -> Self {
+        let (w, h) = self.dimensions();
+        self.reshape(w, h, |i, j| (i, h - 1 - j))
+    }
+
+    /// Mirrored along the diagonal from the top left, so rows become columns
+    pub fn transpose(&self) -> Self {
+        let (w, h) = self.dimensions();
+        self.reshape(h, w, |i, j| (j, i))
+    }
+
+    /// Each cell becomes a block sx wide and sy tall from the same top left, filled by calling
+    /// expand with the cell and each (dx, dy) position within the block
+    pub fn scale<U, F>(&self, sx: isize, sy: isize, expand: F) -> Map<U>
+    where
+        U: Copy + Default,
+        F: Fn(T, isize, isize) -> U,
+    {
+        assert!(
+            sx > 0 && sy > 0,
+            "Scale should be positive, not {sx} by {sy}"
+        );
+        let (w, h) = self.dimensions();
+        let (left, top) = (self.x.start, self.y.start);
+        let mut map = Map::rect((left, top), (left + w * sx - 1, top + h * sy - 1));
+        for (p, value) in self.cells() {
+            let (x, y) = (left + (p.x - left) * sx, top + (p.y - top) * sy);
+            for dy in 0..sy {
+                for dx in 0..sx {
+                    map.write(x + dx, y + dy, expand(value, dx, dy));
+                }
+            }
+        }
+        map
+    }
+
+    /// A Map with the same x() and y() ranges, where each cell within them is f of this Map's
+    /// cell, anywhere else, like unwritten cells of a sparse Map, is the default
+    pub fn convert<U, F>(&self, f: F) -> Map<U>
+    where
+        U: Copy + Default,
+        F: Fn(T) -> U,
+    {
+        let (wide, tall) = (self.x(), self.y());
+        let data = match &self.data {
+            Store::Dense(data) => Store::Dense(
+                data.iter()
+                    .enumerate()
+                    .map(|(i, &value)| {
+                        let (x, y) = self.noitisop(i);
+                        if wide.contains(&x) && tall.contains(&y) {
+                            f(value)
+                        } else {
+                            U::default()
+                        }
+                    })
+                    .collect(),
+            ),
+            Store::Sparse(cells) => {
+                Store::Sparse(cells.iter().map(|(&pos, &value)| (pos, f(value))).collect())
+            }
+        };
+        Map {
+            data,
+            x: self.x,
+            y: self.y,
+            torus: self.torus,
+        }
+    }
+
+    /// Count how many of the cells within the x() and y() ranges match the predicate
+    pub fn count<P>(&self, mut predicate: P) -> usize
+    where
+        P: FnMut(&&T) -> bool,
+    {
+        match &self.data {
+            Store::Dense(_) => self
+                .rows()
+                .map(|row| row.iter().filter(&mut predicate).count())
+                .sum(),
+            Store::Sparse(cells) => {
+                let written = cells.values().filter(&mut predicate).count();
+                if predicate(&&T::default()) {
+                    let (w, h) = self.dimensions();
+                    let unwritten = w as u128 * h as u128 - cells.len() as u128;
+                    let unwritten: usize = unwritten
+                        .try_into()
+                        .expect("Too many unwritten cells to count");
+                    written + unwritten
+                } else {
+                    written
+                }
+            }
+        }
+    }
+
+    /// Obtain a Vec of (x, y) positions within the x() and y() ranges matching the predicate,
+    /// row by row. A sparse Map only looks at cells which were written, so unlike count it
+    /// never finds the default value in cells which weren't
+    pub fn find<P>(&self, predicate: P) -> Vec<(isize, isize)>
+    where
+        P: Fn(T) -> bool,
+    {
+        let mut v = Vec::new();
+
+        match &self.data {
+            Store::Dense(_) => {
+                for (p, value) in self.cells() {
+                    if predicate(value) {
+                        v.push((p.x, p.y));
+                    }
+                }
+            }
+            Store::Sparse(cells) => {
+                for (&pos, &value) in cells {
+                    if predicate(value) {
+                        v.push(pos);
+                    }
+                }
+                v.sort_unstable_by_key(|&(x, y)| (y, x));
+            }
+        }
+        v
+    }
+}
+
+/// Part of a Map, as returned by Map::window, walking the backing store directly
+#[derive(Clone)]
+pub struct Window<'m, T: Copy + Default> {
+    map: &'m Map<T>,
+    x: RangeInclusive<isize>,
+    y: RangeInclusive<isize>,
+}
+
+impl<'m, T: Copy + Default> Window<'m, T> {
+    pub fn x(&self) -> RangeInclusive<isize> {
+        self.x.clone()
+    }
+
+    pub fn y(&self) -> RangeInclusive<isize> {
+        self.y.clone()
+    }
+
+    /// Reads an (x, y) position, None if that's outside the Window
+    pub fn read(&self, x: isize, y: isize) -> Option<T> {
+        if self.x.contains(&x) && self.y.contains(&y) {
+            self.map.read(x, y)
+        } else {
+            None
+        }
+    }
+
+    /// Each row of the Window, top to bottom, borrowed unless the Map is sparse
+    pub fn rows(&self) -> impl Iterator<Item = Cow<'m, [T]>> + 'm {
+        let map = self.map;
+        let x = self.x.clone();
+        let width = if x.is_empty() {
+            0
+        } else {
+            (x.end() - x.start() + 1) as usize
+        };
+        self.y.clone().map(move |y| match &map.data {
+            Store::Dense(_) if width == 0 => Cow::Borrowed(&[][..]),
+            Store::Dense(data) => {
+                let from = map.position(*x.start(), y);
+                Cow::Borrowed(&data[from..from + width])
+            }
+            Store::Sparse(_) => x
+                .clone()
+                .map(|x| map.read(x, y).unwrap_or_default())
+                .collect(),
+        })
+    }
+
+    /// Each column of the Window, left to right, each top to bottom
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = T> + 'm> + 'm {
+        let map = self.map;
+        let top = *self.y.start();
+        let height = self.y.clone().count();
+        // Each row of a dense Map is a whole row of the backing store further along
+        let stride = map.x.size as usize;
+        self.x.clone().map(move |x| {
+            let from = match &map.data {
+                Store::Dense(_) if height > 0 => map.position(x, top),
+                _ => 0,
+            };
+            (0..height).map(move |n| match &map.data {
+                Store::Dense(data) => data[from + n * stride],
+                Store::Sparse(_) => map.read(x, top + n as isize).unwrap_or_default(),
+            })
+        })
+    }
+
+    /// Every cell of the Window with its position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (Point, T)> + 'm {
+        let left = *self.x.start();
+        self.y.clone().zip(self.rows()).flat_map(move |(y, row)| {
+            (0..row.len()).map(move |n| (Point::new(left + n as isize, y), row[n]))
+        })
+    }
+
+    /// Count how many of the cells in the Window match the predicate
+    pub fn count<P>(&self, predicate: P) -> usize
+    where
+        P: Fn(T) -> bool,
+    {
+        self.rows()
+            .map(|row| row.iter().filter(|&&value| predicate(value)).count())
+            .sum()
+    }
+}
+
+/// A connected area of a Map, as found by Map::regions
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Region {
+    /// Where the Region was first found
+    pub start: Point,
+    pub area: usize,
+    /// Edges between a cell in this Region and a cell which isn't
+    pub perimeter: usize,
+    /// Corners of the Region, inside and outside, which is also how many straight sides it has
+    pub corners: usize,
+    /// Top left of the bounding box
+    pub min: Point,
+    /// Bottom right of the bounding box, inclusive
+    pub max: Point,
+}
+
+impl Region {
+    fn measure<I>(cells: &[Point], inside: I) -> Self
+    where
+        I: Fn(Point) -> bool,
+    {
+        let start = cells[0];
+        let mut region = Region {
+            start,
+            area: cells.len(),
+            perimeter: 0,
+            corners: 0,
+            min: start,
+            max: start,
+        };
+        for &p in cells {
+            region.min = Point::new(region.min.x.min(p.x), region.min.y.min(p.y));
+            region.max = Point::new(region.max.x.max(p.x), region.max.y.max(p.y));
+            for dir in Direction::ALL {
+                let side = inside(p + dir);
+                if !side {
+                    region.perimeter += 1;
+                }
+                // Each pair of sides either side of a corner of this cell
+                let turn = dir.clockwise();
+                let other = inside(p + turn);
+                let diagonal = inside(p + dir + turn);
+                if (!side && !other) || (side && other && !diagonal) {
+                    region.corners += 1;
+                }
+            }
+        }
+        region
+    }
+}
+
+/// Like a Map, but with N dimensions instead of just two, positions are [x, y, z, ...]
+/// The backing store automatically grows as necessary, just as it does for a Map
+#[derive(Clone)]
+pub struct Grid<T: Copy + Default, const N: usize> {
+    data: Vec<T>,
+    planes: [Plane; N],
+}
+
+impl<T: Copy + Default, const N: usize> Default for Grid<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Grid<T, N> {
+    /// Grid initially covering each of the ranges, but it will grow automatically as necessary
+    pub fn ranged(ranges: [RangeInclusive<isize>; N]) -> Self {
+        let planes = ranges.map(|r| {
+            let (from, to) = r.into_inner();
+            Plane::from_to(from, to)
+        });
+        let size = planes.iter().map(|p| p.size as usize).product();
+        Self {
+            data: vec![T::default(); size],
+            planes,
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::ranged(std::array::from_fn(|_| -4..=4))
+    }
+
+    /// Range of values along each axis, as for Map::x() and Map::y() this may include some
+    /// "dead" space
+    pub fn ranges(&self) -> [RangeInclusive<isize>; N] {
+        self.planes.each_ref().map(|p| p.start..=p.end)
+    }
+
+    fn inbound(&self, pos: [isize; N]) -> bool {
+        self.planes.iter().zip(pos).all(|(p, n)| p.inbound(n))
+    }
+
+    fn position(&self, pos: [isize; N]) -> usize {
+        let mut posn = 0;
+        for (p, n) in self.planes.iter().zip(pos).rev() {
+            posn = posn * p.size + (n - p.offset);
+        }
+        posn as usize
+    }
+
+    /// Every position within the ranges(), the first axis changing fastest
+    fn positions(&self) -> impl Iterator<Item = [isize; N]> + '_ {
+        let extents = self.planes.each_ref().map(|p| p.end - p.start + 1);
+        let total: isize = extents.iter().product();
+        (0..total).map(move |mut rest| {
+            std::array::from_fn(|k| {
+                let n = self.planes[k].start + rest % extents[k];
+                rest /= extents[k];
+                n
+            })
+        })
+    }
+
+    /// Grow Grid by suitably expanding every plane and re-allocating, then copying only the
+    /// cells within the ranges(), since the dead space around them needn't fit
+    fn grow(&mut self, pos: [isize; N]) {
+        let mut bigger = Self {
+            data: Vec::new(),
+            planes: std::array::from_fn(|k| self.planes[k].expand(pos[k])),
+        };
+        let size = bigger.planes.iter().map(|p| p.size as usize).product();
+        bigger.data = vec![T::default(); size];
+        for there in self.positions() {
+            let posn = bigger.position(there);
+            bigger.data[posn] = self.data[self.position(there)];
+        }
+        *self = bigger;
+    }
+
+    fn include(&mut self, pos: [isize; N]) {
+        if self.inbound(pos) {
+            for (p, n) in self.planes.iter_mut().zip(pos) {
+                p.start = p.start.min(n);
+                p.end = p.end.max(n);
+            }
+        } else {
+            self.grow(pos);
+        }
+    }
+
+    /// Write at pos in the Grid, this will grow the Grid automatically
+    pub fn write(&mut self, pos: [isize; N], value: T) {
+        self.include(pos);
+        let posn = self.position(pos);
+        self.data[posn] = value;
+    }
+
+    /// Reads pos in the Grid, but can be None if that position wasn't yet mapped
+    pub fn read(&self, pos: [isize; N]) -> Option<T> {
+        if self.inbound(pos) {
+            Some(self.data[self.position(pos)])
+        } else {
+            None
+        }
+    }
+
+    /// Each cell at one of the offsets from pos, with what read finds there
+    pub fn neighbours<'g>(
+        &'g self,
+        pos: [isize; N],
+        offsets: &'g [[isize; N]],
+    ) -> impl Iterator<Item = ([isize; N], Option<T>)> + 'g {
+        offsets.iter().map(move |offset| {
+            let there = std::array::from_fn(|k| pos[k] + offset[k]);
+            (there, self.read(there))
+        })
+    }
+
+    /// The 2N neighbours which differ from pos by one step along a single axis
+    pub fn orthogonal(
+        &self,
+        pos: [isize; N],
+    ) -> impl Iterator<Item = ([isize; N], Option<T>)> + '_ {
+        (0..N).flat_map(move |k| {
+            [-1, 1].map(|step| {
+                let mut there = pos;
+                there[k] += step;
+                (there, self.read(there))
+            })
+        })
+    }
+
+    /// All 3^N - 1 neighbours which differ from pos by at most one step along every axis
+    pub fn surrounding(
+        &self,
+        pos: [isize; N],
+    ) -> impl Iterator<Item = ([isize; N], Option<T>)> + '_ {
+        (0..3_usize.pow(N as u32))
+            .map(move |mut n| {
+                std::array::from_fn(|k| {
+                    let step = (n % 3) as isize - 1;
+                    n /= 3;
+                    pos[k] + step
+                })
+            })
+            .filter(move |&there| there != pos)
+            .map(|there| (there, self.read(there)))
+    }
+
+    /// Count how many of the positions within the ranges() match the predicate
+    pub fn count<P>(&self, mut predicate: P) -> usize
+    where
+        P: FnMut(&&T) -> bool,
+    {
+        self.positions()
+            .filter(|&pos| predicate(&&self.data[self.position(pos)]))
+            .count()
+    }
+
+    /// Obtain a Vec of positions within the ranges() matching the predicate
+    pub fn find<P>(&self, predicate: P) -> Vec<[isize; N]>
+    where
+        P: Fn(T) -> bool,
+    {
+        self.positions()
+            .filter(|&pos| predicate(self.data[self.position(pos)]))
+            .collect()
+    }
+}
+
+/// A Map of booleans packed 64 to a word, each row starting on a fresh word
+/// Like a Map, there's room to grow around the x() and y() ranges, and every bit outside them is
+/// always clear, so whole words can be counted, copied and combined
+#[derive(Clone, Debug)]
+pub struct BitMap {
+    words: Vec<u64>,
+    x: Plane,
+    y: Plane,
+    stride: usize,
+}
+
+impl BitMap {
+    /// A BitMap with every cell from (x1, y1) to (x2, y2) inclusive clear, it will grow
+    /// automatically if written outside that
+    pub fn ranged(x: RangeInclusive<isize>, y: RangeInclusive<isize>) -> Self {
+        let (x1, x2) = x.into_inner();
+        let (y1, y2) = y.into_inner();
+        if x2 < x1 {
+            panic!("{x1} must be less than or equal to {x2}");
+        }
+        if y2 < y1 {
+            panic!("{y1} must be less than or equal to {y2}");
+        }
+        let mut x = Plane::from_to(x1, x2);
+        let mut y = Plane::from_to(y1, y2);
+        (x.start, x.end) = (x1, x2);
+        (y.start, y.end) = (y1, y2);
+        Self::room(x, y)
+    }
+
+    /// Clear BitMap with room for the planes
+    fn room(x: Plane, y: Plane) -> Self {
+        let stride = (x.size as usize).div_ceil(64);
+        Self {
+            words: vec![0; stride * y.size as usize],
+            x,
+            y,
+            stride,
+        }
+    }
+
     pub fn x(&self) -> RangeInclusive<isize> {
         self.x.start..=self.x.end
     }
 
-    /// Range of Y values, it is possible that this range includes some "dead" space
-    /// but if the Map was built by parsing a string, this will be the exact size
     pub fn y(&self) -> RangeInclusive<isize> {
         self.y.start..=self.y.end
     }
 
-    fn noitisop(&self, i: usize) -> (isize, isize) {
-        let y = self.y.offset + (i as isize / self.x.size);
-        let x = self.x.offset + (i as isize % self.x.size);
-        (x, y)
+    /// Which word has the bit for (x, y), and the mask for that bit, if it's inside
+    fn locate(&self, x: isize, y: isize) -> Option<(usize, u64)> {
+        if !self.x().contains(&x) || !self.y().contains(&y) {
+            return None;
+        }
+        let col = (x - self.x.offset) as usize;
+        let row = (y - self.y.offset) as usize;
+        Some((row * self.stride + col / 64, 1 << (col % 64)))
     }
 
-    fn position(&self, x: isize, y: isize) -> usize {
-        let posn = (y - self.y.offset) * self.x.size + (x - self.x.offset);
-        posn as usize
+    /// The 64 cells from (x, y) rightwards as bits, with any outside the BitMap clear
+    fn bits(&self, x: isize, y: isize) -> u64 {
+        if !self.y.inbound(y) {
+            return 0;
+        }
+        let from = (y - self.y.offset) as usize * self.stride;
+        bits_from(&self.words[from..from + self.stride], x - self.x.offset)
     }
 
-    /// Write to (x, y) in the Map, this will grow the map automatically
-    pub fn write(&mut self, x: isize, y: isize, value: T) {
-        self.include(x, y);
-        let posn = self.position(x, y);
-        self.data[posn] = value;
+    /// The bits of word i in each row which are within the x() range
+    fn mask(&self, i: usize) -> u64 {
+        let first = self.x.start - self.x.offset - i as isize * 64;
+        let last = self.x.end - self.x.offset - i as isize * 64;
+        if first > 63 || last < 0 {
+            return 0;
+        }
+        let high = if last >= 63 {
+            !0
+        } else {
+            (1 << (last + 1)) - 1
+        };
+        high & (!0 << first.max(0))
     }
 
-    /// Reads an (x, y) position on the Map, but can be None if that position wasn't yet mapped
-    /// Use or(value) or or_else(function) if appropriate
-    pub fn read(&self, x: isize, y: isize) -> Option<T> {
-        if self.inbound(x, y) {
-            Some(self.data[self.position(x, y)])
+    /// Whether (x, y) is set, None if that position is outside the BitMap
+    pub fn read(&self, x: isize, y: isize) -> Option<bool> {
+        self.locate(x, y)
+            .map(|(word, mask)| self.words[word] & mask != 0)
+    }
+
+    /// Set or clear (x, y), this will grow the BitMap automatically
+    pub fn write(&mut self, x: isize, y: isize, value: bool) {
+        if self.locate(x, y).is_none() {
+            self.include(x, y);
+        }
+        let (word, mask) = self.locate(x, y).expect("BitMap grew to include this cell");
+        if value {
+            self.words[word] |= mask;
         } else {
-            None
+            self.words[word] &= !mask;
         }
     }
 
-    /// Count how many of the mapped positions match the predicate
-    pub fn count<P>(&self, predicate: P) -> usize
-    where
-        P: FnMut(&&T) -> bool,
-    {
-        self.data.iter().filter(predicate).count()
+    /// Stretch the x() and y() ranges to include (x, y), making more room if there isn't enough
+    fn include(&mut self, x: isize, y: isize) {
+        if self.x.inbound(x) && self.y.inbound(y) {
+            (self.x.start, self.x.end) = (self.x.start.min(x), self.x.end.max(x));
+            (self.y.start, self.y.end) = (self.y.start.min(y), self.y.end.max(y));
+            return;
+        }
+        let mut bigger = Self::room(self.x.expand(x), self.y.expand(y));
+        for row in self.y() {
+            let to = (row - bigger.y.offset) as usize * bigger.stride;
+            for (i, word) in bigger.words[to..to + bigger.stride].iter_mut().enumerate() {
+                *word = self.bits(bigger.x.offset + i as isize * 64, row);
+            }
+        }
+        *self = bigger;
     }
 
-    /// Obtain a Vec of (x, y) positions matching the predicate
-    pub fn find<P>(&self, predicate: P) -> Vec<(isize, isize)>
-    where
-        P: Fn(T) -> bool,
-    {
-        let mut v = Vec::new();
+    /// Like read, but at a Point
+    pub fn read_at(&self, p: Point) -> Option<bool> {
+        self.read(p.x, p.y)
+    }
+
+    /// Like write, but at a Point
+    pub fn write_at(&mut self, p: Point, value: bool) {
+        self.write(p.x, p.y, value);
+    }
+
+    /// How many cells are set
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
 
-        for (i, &value) in self.data.iter().enumerate() {
-            if predicate(value) {
-                let (x, y) = self.noitisop(i);
-                v.push((x, y));
+    /// Obtain a Vec of (x, y) positions which are set, row by row
+    pub fn find(&self) -> Vec<(isize, isize)> {
+        let mut v = Vec::with_capacity(self.count());
+        for (i, &word) in self.words.iter().enumerate() {
+            let (row, col) = (i / self.stride, (i % self.stride) * 64);
+            let mut bits = word;
+            while bits != 0 {
+                let n = bits.trailing_zeros() as usize;
+                v.push((
+                    self.x.offset + (col + n) as isize,
+                    self.y.offset + row as isize,
+                ));
+                bits &= bits - 1;
             }
         }
         v
     }
+
+    /// Word by word, op of this BitMap and other, over the extent of this BitMap, treating cells
+    /// outside other as clear
+    fn combine(&self, other: &Self, op: fn(u64, u64) -> u64) -> Self {
+        let mut out = self.clone();
+        for row in self.y() {
+            let from = (row - self.y.offset) as usize * self.stride;
+            let words = &mut out.words[from..from + self.stride];
+            for (i, word) in words.iter_mut().enumerate() {
+                let theirs = other.bits(self.x.offset + i as isize * 64, row);
+                *word = op(*word, theirs) & self.mask(i);
+            }
+        }
+        out
+    }
+
+    /// Cells set in either BitMap
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Cells set in both BitMaps
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Cells set in this BitMap but not in other
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Every set cell moved by (dx, dy) within the same extent, any which would leave are lost
+    /// Combining shifts in each direction finds neighbours for a whole generation at once
+    pub fn shift(&self, dx: isize, dy: isize) -> Self {
+        let mut out = Self::room(self.x, self.y);
+        for row in self.y() {
+            let to = (row - self.y.offset) as usize * self.stride;
+            let words = &mut out.words[to..to + self.stride];
+            for (i, word) in words.iter_mut().enumerate() {
+                let x = self.x.offset + i as isize * 64;
+                *word = self.bits(x - dx, row - dy) & self.mask(i);
+            }
+        }
+        out
+    }
+}
+
+/// BitMaps are equal when they have the same x() and y() ranges with the same cells set,
+/// however much room each has to grow
+impl PartialEq for BitMap {
+    fn eq(&self, other: &Self) -> bool {
+        let left = self.x.start;
+        (self.x(), self.y()) == (other.x(), other.y())
+            && self.y().all(|y| {
+                (left..=self.x.end)
+                    .step_by(64)
+                    .all(|x| self.bits(x, y) == other.bits(x, y))
+            })
+    }
+}
+
+impl Eq for BitMap {}
+
+/// The 64 bits of words starting at bit number start, which may be outside the words
+fn bits_from(words: &[u64], start: isize) -> u64 {
+    let word = |n: isize| {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| words.get(n))
+            .copied()
+            .unwrap_or(0)
+    };
+    let (q, r) = (start.div_euclid(64), start.rem_euclid(64));
+    if r == 0 {
+        word(q)
+    } else {
+        word(q) >> r | word(q + 1) << (64 - r)
+    }
+}
+
+impl BitOr for &BitMap {
+    type Output = BitMap;
+
+    fn bitor(self, other: Self) -> BitMap {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &BitMap {
+    type Output = BitMap;
+
+    fn bitand(self, other: Self) -> BitMap {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &BitMap {
+    type Output = BitMap;
+
+    fn sub(self, other: Self) -> BitMap {
+        self.difference(other)
+    }
 }
 
+/// How each symbol in a textual map corresponds to a cell
 pub trait Legend: Copy {
     fn from_char(ch: char) -> Self;
+
+    /// None if ch isn't part of this legend, parsing a Map reports it as a BadCell.
+    /// Legends which can reject symbols override this, the default accepts everything
+    fn try_from_char(ch: char) -> Option<Self> {
+        Some(Self::from_char(ch))
+    }
+
     fn to_char(self) -> char;
 }
 
+/// A symbol which wasn't in the Legend, found while parsing a Map at (x, y)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BadCell {
+    pub ch: char,
+    pub x: isize,
+    pub y: isize,
+}
+
+impl fmt::Display for BadCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unexpected {:?} on map at ({}, {})",
+            self.ch, self.x, self.y
+        )
+    }
+}
+
+impl std::error::Error for BadCell {}
+
 use std::fmt;
 impl<T: Legend + Default> fmt::Debug for Map<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -241,17 +1450,9 @@ impl<T: Legend + Default> fmt::Debug for Map<T> {
             self.y.end,
             self.y.offset + self.y.size
         )?;
-        let from_y = self.y.start - self.y.offset;
-        let from_x = self.x.start - self.x.offset;
-        let to_y = self.y.size - (self.y.offset + self.y.size - self.y.end);
-        let to_x = self.x.size - (self.x.offset + self.x.size - self.x.end);
-        if self.data.is_empty() {
-            return Ok(());
-        }
-        for row in from_y..=to_y {
-            for col in from_x..=to_x {
-                let posn = row * self.x.size + col;
-                let ch = self.data[posn as usize].to_char();
+        for row in self.rows() {
+            for cell in row.iter() {
+                let ch = cell.to_char();
                 write!(f, "{ch}")?;
             }
             f.write_str("\n")?;
@@ -262,17 +1463,9 @@ impl<T: Legend + Default> fmt::Debug for Map<T> {
 
 impl<T: fmt::Display + Copy + Default> fmt::Display for Map<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let from_y = self.y.start - self.y.offset;
-        let from_x = self.x.start - self.x.offset;
-        let to_y = self.y.size - (self.y.offset + self.y.size - self.y.end);
-        let to_x = self.x.size - (self.x.offset + self.x.size - self.x.end);
-        if self.data.is_empty() {
-            return Ok(());
-        }
-        for row in from_y..=to_y {
-            for col in from_x..=to_x {
-                let posn = row * self.x.size + col;
-                let s = format!("{}", self.data[posn as usize]);
+        for row in self.rows() {
+            for cell in row.iter() {
+                let s = format!("{cell}");
                 f.write_str(&s)?;
             }
             f.write_str("\n")?;
@@ -281,20 +1474,147 @@ impl<T: fmt::Display + Copy + Default> fmt::Display for Map<T> {
     }
 }
 
-use std::convert::Infallible;
+/// Set cells are shown as # and clear cells as .
+impl fmt::Display for BitMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in self.y() {
+            for x in self.x() {
+                let set = self.read(x, y).unwrap_or_default();
+                f.write_str(if set { "#" } else { "." })?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps are equal when they have the same x() and y() ranges with the same cells inside them,
+/// however much room each has to grow and whether or not it is sparse
+impl<T: PartialEq + Copy + Default> PartialEq for Map<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x() == other.x() && self.y() == other.y() && self.rows().eq(other.rows())
+    }
+}
+
+impl<T: Eq + Copy + Default> Eq for Map<T> {}
+
+impl<T: Hash + Copy + Default> Hash for Map<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x().hash(state);
+        self.y().hash(state);
+        for row in self.rows() {
+            row.hash(state);
+        }
+    }
+}
+
+impl<T: PartialEq + Copy + Default> Map<T> {
+    /// Each position within the x() and y() ranges of either Map where they differ, row by row,
+    /// with what read finds there in this Map and then the other
+    pub fn diff(&self, other: &Self) -> Vec<(Point, Option<T>, Option<T>)> {
+        let (wide, tall) = self.union(other);
+        let (this, that) = (self.whole(), other.whole());
+        let mut v = Vec::new();
+        for y in tall {
+            for x in wide.clone() {
+                let (ours, theirs) = (this.read(x, y), that.read(x, y));
+                if ours != theirs {
+                    v.push((Point::new(x, y), ours, theirs));
+                }
+            }
+        }
+        v
+    }
+
+    /// Ranges covering the x() and y() ranges of both Maps
+    fn union(&self, other: &Self) -> (RangeInclusive<isize>, RangeInclusive<isize>) {
+        let wide = self.x.start.min(other.x.start)..=self.x.end.max(other.x.end);
+        let tall = self.y.start.min(other.y.start)..=self.y.end.max(other.y.end);
+        (wide, tall)
+    }
+}
+
+impl<T: Legend + PartialEq + Default> Map<T> {
+    /// This Map and the other side by side, then a third panel with * marking each difference
+    /// Anywhere outside one of the Maps is shown as a space
+    pub fn compare(&self, other: &Self) -> String {
+        let (wide, tall) = self.union(other);
+        let (this, that) = (self.whole(), other.whole());
+        let show = |cell: Option<T>| cell.map_or(' ', Legend::to_char);
+        let mut s = String::new();
+        for y in tall {
+            let ours: String = wide.clone().map(|x| show(this.read(x, y))).collect();
+            let theirs: String = wide.clone().map(|x| show(that.read(x, y))).collect();
+            let marks: String = wide
+                .clone()
+                .map(|x| {
+                    if this.read(x, y) == that.read(x, y) {
+                        ' '
+                    } else {
+                        '*'
+                    }
+                })
+                .collect();
+            s.push_str(&format!("{ours} | {theirs} | {}\n", marks.trim_end()));
+        }
+        s
+    }
+}
+
+/// Each x by y slice in turn, labelled with where it is along the other axes when there are any
+impl<T: fmt::Display + Copy + Default, const N: usize> fmt::Display for Grid<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const AXES: [char; 4] = ['x', 'y', 'z', 'w'];
+        let ranges = self.ranges();
+        let mut pos = ranges.clone().map(|r| *r.start());
+        loop {
+            if N > 2 {
+                let labels: Vec<String> = (2..N)
+                    .map(|k| match AXES.get(k) {
+                        Some(axis) => format!("{axis}={}", pos[k]),
+                        None => format!("#{k}={}", pos[k]),
+                    })
+                    .collect();
+                writeln!(f, "{}", labels.join(", "))?;
+            }
+            for y in ranges.get(1).cloned().unwrap_or(0..=0) {
+                if N > 1 {
+                    pos[1] = y;
+                }
+                for x in ranges[0].clone() {
+                    pos[0] = x;
+                    let cell = self.read(pos).unwrap_or_default();
+                    write!(f, "{cell}")?;
+                }
+                f.write_str("\n")?;
+            }
+            // Move on to the next slice, like an odometer
+            let Some(k) = (2..N).find(|&k| pos[k] < *ranges[k].end()) else {
+                return Ok(());
+            };
+            pos[k] += 1;
+            for j in 2..k {
+                pos[j] = *ranges[j].start();
+            }
+            f.write_str("\n")?;
+        }
+    }
+}
+
 use std::str::FromStr;
 impl<T> FromStr for Map<T>
 where
     T: Legend + Default,
 {
-    type Err = Infallible;
+    type Err = BadCell;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut map = Self::rect((0, 0), (0, 0));
         for (row, line) in s.lines().enumerate() {
             for (col, ch) in line.chars().enumerate() {
-                let item = Legend::from_char(ch);
-                map.write(col as isize, row as isize, item);
+                let (x, y) = (col as isize, row as isize);
+                let item = T::try_from_char(ch).ok_or(BadCell { ch, x, y })?;
+                map.write(x, y, item);
             }
         }
         Ok(map)
@@ -302,9 +1622,9 @@ where
 }
 
 #[cfg(test)]
-
 mod tests {
-    use crate::map::{Legend, Map, Plane};
+    use crate::map::{BadCell, BitMap, Direction, Grid, Legend, Map, Plane, Point, SURROUNDING};
+    use std::hash::{BuildHasher, RandomState};
 
     #[derive(Copy, Clone, Debug, Default, PartialEq)]
     enum Maze {
@@ -463,4 +1783,458 @@ mod tests {
         map.write(1, 1, Maze::Us);
         assert_eq!(map.count(|&m| m == &Maze::Space), 22);
     }
+
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+    struct Digit(u32);
+
+    impl Legend for Digit {
+        fn from_char(ch: char) -> Self {
+            Self::try_from_char(ch).unwrap_or_else(|| panic!("Impossible '{ch}'"))
+        }
+
+        fn try_from_char(ch: char) -> Option<Self> {
+            ch.to_digit(10).map(Digit)
+        }
+
+        fn to_char(self) -> char {
+            char::from_digit(self.0, 10).unwrap_or('?')
+        }
+    }
+
+    #[test]
+    fn bad_cell() {
+        let map: Map<Digit> = "123\n456\n".parse().unwrap();
+        assert_eq!(map.read(2, 1), Some(Digit(6)));
+        let bad = "123\n4x6\n".parse::<Map<Digit>>();
+        assert_eq!(
+            bad.unwrap_err(),
+            BadCell {
+                ch: 'x',
+                x: 1,
+                y: 1
+            }
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let map: Map<Maze> = MAZE.parse().unwrap();
+        let spaces = map
+            .orthogonal(1, 1)
+            .filter(|&(_, m)| m == Some(Maze::Space))
+            .count();
+        assert_eq!(spaces, 2);
+        assert_eq!(map.diagonal(1, 1).next(), Some(((0, 0), Some(Maze::Wall))));
+        assert_eq!(map.surrounding(4, 4).count(), 8);
+        assert_eq!(map.bounded_neighbours(0, 0, &SURROUNDING).count(), 3);
+        let knight = [(1, 2), (2, 1), (-1, 2)];
+        let found: Vec<_> = map.bounded_neighbours(0, 0, &knight).collect();
+        assert_eq!(found, vec![((1, 2), Maze::Space), ((2, 1), Maze::Space)]);
+    }
+
+    #[test]
+    fn points() {
+        let p = Point::new(3, 4);
+        assert_eq!(p + Point::new(1, -1), Point::new(4, 3));
+        assert_eq!(p - Point::new(1, -1), Point::new(2, 5));
+        assert_eq!(-p * 2, Point::new(-6, -8));
+        assert_eq!(p + Direction::North, Point::new(3, 3));
+        assert_eq!(p.manhattan(Point::new(-1, 6)), 6);
+        assert_eq!(Point::from((3, 4)), p);
+        assert_eq!(<(isize, isize)>::from(p), (3, 4));
+    }
+
+    #[test]
+    fn directions() {
+        for d in Direction::ALL {
+            assert_eq!(d.clockwise().anticlockwise(), d);
+            assert_eq!(d.clockwise().clockwise(), d.opposite());
+            assert_eq!(Direction::from_char(d.to_char()), Some(d));
+            assert_eq!(d.offset() + d.opposite().offset(), Point::default());
+        }
+        assert_eq!(Direction::from_char('W'), Some(Direction::West));
+        assert_eq!(Direction::from_char('x'), None);
+    }
+
+    #[test]
+    fn map_points() {
+        let mut map: Map<Maze> = MAZE.parse().unwrap();
+        let p = Point::new(1, 1);
+        assert_eq!(map.read_at(p), Some(Maze::Space));
+        map.write_at(p, Maze::Us);
+        assert_eq!(map.find_points(|m| m == Maze::Us), vec![p]);
+    }
+
+    #[test]
+    fn regions() {
+        let map: Map<Digit> = "112\n122\n333\n".parse().unwrap();
+        let (labels, regions) = map.regions(|a, b| a == b);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(labels.read(2, 1), Some(Some(1)));
+        let one = regions[0];
+        assert_eq!((one.area, one.perimeter, one.corners), (3, 8, 6));
+        assert_eq!((one.min, one.max), (Point::new(0, 0), Point::new(1, 1)));
+        let three = regions[2];
+        assert_eq!((three.area, three.perimeter, three.corners), (3, 8, 4));
+        assert_eq!(three.start, Point::new(0, 2));
+
+        let (_, regions) = map.regions(|a, b| (a.0 < 3) == (b.0 < 3));
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            (regions[0].area, regions[0].perimeter, regions[0].corners),
+            (6, 10, 4)
+        );
+        assert_eq!(map.flood(Point::new(2, 1), |a, b| a == b).len(), 3);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let map: Map<Digit> = "123\n456\n".parse().unwrap();
+        let rows: Vec<Vec<u32>> = map
+            .rows()
+            .map(|row| row.iter().map(|d| d.0).collect())
+            .collect();
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let columns: Vec<Vec<u32>> = map
+            .columns()
+            .map(|col| col.map(|d| d.0).collect())
+            .collect();
+        assert_eq!(columns, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        let cells: Vec<(Point, Digit)> = map.cells().collect();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[4], (Point::new(1, 1), Digit(5)));
+    }
+
+    #[test]
+    fn window() {
+        let map: Map<Maze> = MAZE.parse().unwrap();
+        let window = map.window(1..=3, 1..=3);
+        assert_eq!(window.count(|m| m == Maze::Space), 7);
+        assert_eq!(window.read(0, 0), None);
+        assert_eq!(window.read(3, 3), Some(Maze::Space));
+        assert_eq!(window.cells().next(), Some((Point::new(1, 1), Maze::Space)));
+        for (x, column) in window.x().zip(window.columns()) {
+            let expected: Vec<Maze> = window.y().map(|y| map.read(x, y).unwrap()).collect();
+            assert_eq!(column.collect::<Vec<_>>(), expected);
+        }
+        let clipped = map.window(-5..=1, 5..=20);
+        assert_eq!((clipped.x(), clipped.y()), (0..=1, 5..=6));
+        assert_eq!(clipped.rows().count(), 2);
+        assert_eq!(map.window(20..=30, 0..=1).cells().count(), 0);
+    }
+
+    fn digits(map: &Map<Digit>) -> Vec<Vec<u32>> {
+        map.rows()
+            .map(|row| row.iter().map(|d| d.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn transforms() {
+        let map: Map<Digit> = "12\n34\n56\n".parse().unwrap();
+        assert_eq!(
+            digits(&map.rotate(0)),
+            vec![vec![1, 2], vec![3, 4], vec![5, 6]]
+        );
+        assert_eq!(digits(&map.rotate(1)), vec![vec![5, 3, 1], vec![6, 4, 2]]);
+        assert_eq!(
+            digits(&map.rotate(2)),
+            vec![vec![6, 5], vec![4, 3], vec![2, 1]]
+        );
+        assert_eq!(digits(&map.rotate(3)), vec![vec![2, 4, 6], vec![1, 3, 5]]);
+        assert_eq!(digits(&map.rotate(5)), digits(&map.rotate(1)));
+        assert_eq!(
+            digits(&map.flip_horizontal()),
+            vec![vec![2, 1], vec![4, 3], vec![6, 5]]
+        );
+        assert_eq!(
+            digits(&map.flip_vertical()),
+            vec![vec![5, 6], vec![3, 4], vec![1, 2]]
+        );
+        assert_eq!(digits(&map.transpose()), vec![vec![1, 3, 5], vec![2, 4, 6]]);
+        let rotated = map.rotate(1);
+        assert_eq!((rotated.x(), rotated.y()), (0..=2, 0..=1));
+    }
+
+    #[test]
+    fn scale() {
+        let map: Map<Digit> = "12\n".parse().unwrap();
+        let big = map.scale(2, 3, |d, dx, dy| Digit(d.0 * 10 + (dx + dy) as u32));
+        assert_eq!((big.x(), big.y()), (0..=3, 0..=2));
+        assert_eq!(
+            digits(&big),
+            vec![
+                vec![10, 11, 20, 21],
+                vec![11, 12, 21, 22],
+                vec![12, 13, 22, 23]
+            ]
+        );
+    }
+
+    #[test]
+    fn convert() {
+        let map: Map<Digit> = "12\n34\n".parse().unwrap();
+        let odd = map.convert(|d| d.0 % 2 == 1);
+        assert_eq!((odd.x(), odd.y()), (map.x(), map.y()));
+        assert_eq!(odd.find(|b| b), vec![(0, 0), (0, 1)]);
+
+        // Room to grow stays default, so growing into it doesn't find converted cells
+        let mut roomy: Map<Digit> = Map::new();
+        roomy.write(0, 0, Digit(1));
+        let mut bigger = roomy.convert(|d| Digit(d.0 + 1));
+        bigger.write(1, 0, Digit(5));
+        assert_eq!(digits(&bigger), vec![vec![2, 5]]);
+
+        let mut ring: Map<u8> = Map::torus(3, 1);
+        ring.write(1, 0, 4);
+        let ring = ring.convert(|n| Digit(n as u32));
+        assert_eq!(ring.read(4, 0), Some(Digit(4)));
+    }
+
+    #[test]
+    fn torus() {
+        let mut map: Map<u8> = Map::torus(5, 3);
+        assert_eq!((map.x(), map.y()), (0..=4, 0..=2));
+        map.write(-1, 0, 1);
+        map.write(7, -4, 2);
+        assert_eq!(map.read(4, 0), Some(1));
+        assert_eq!(map.read(2, 2), Some(2));
+        assert_eq!(map.read(-3, 5), Some(2));
+        assert_eq!((map.x(), map.y()), (0..=4, 0..=2));
+        assert_eq!(map.bounded_neighbours(0, 0, &SURROUNDING).count(), 8);
+        assert_eq!(map.orthogonal(0, 0).last(), Some(((4, 0), Some(1))));
+    }
+
+    #[test]
+    fn sparse() {
+        const FAR: isize = 1_000_000_000;
+        let mut map: Map<u8> = Map::sparse();
+        map.write(0, 0, 1);
+        map.write(FAR, -FAR, 2);
+        map.write(-3, 4, 1);
+        assert_eq!((map.x(), map.y()), (-3..=FAR, -FAR..=4));
+        assert_eq!(map.read(FAR, -FAR), Some(2));
+        assert_eq!(map.read(5, 3), Some(0));
+        assert_eq!(map.read(FAR + 1, 0), None);
+        assert_eq!(map.count(|&&n| n == 1), 2);
+        assert_eq!(map.find(|n| n > 0), vec![(FAR, -FAR), (0, 0), (-3, 4)]);
+
+        let maze: Map<Maze> = MAZE.parse().unwrap();
+        let mut copy: Map<Maze> = Map::sparse();
+        for (p, cell) in maze.cells() {
+            copy.write_at(p, cell);
+        }
+        assert!(copy.rows().eq(maze.rows()));
+        assert_eq!(
+            copy.count(|&&m| m == Maze::Space),
+            maze.count(|&&m| m == Maze::Space)
+        );
+
+        let mut small: Map<Digit> = Map::sparse();
+        small.write(1, 0, Digit(2));
+        small.write(0, 1, Digit(3));
+        assert_eq!(
+            format!("{small:?}").lines().skip(1).collect::<Vec<_>>(),
+            ["02", "30"]
+        );
+        assert_eq!(digits(&small.rotate(1)), vec![vec![3, 0], vec![0, 2]]);
+    }
+
+    #[test]
+    fn sparse_extent() {
+        let mut map: Map<u8> = Map::sparse();
+        assert_eq!(map.read(0, 0), None);
+        assert!(map.x().is_empty() && map.y().is_empty());
+        assert_eq!(map.count(|_| true), 0);
+        assert_eq!(map.rotate(1).count(|_| true), 0);
+
+        // Too far apart to number every cell, but counting the unwritten ones is fine
+        const FAR: isize = 10_000_000_000;
+        map.write(-FAR, 7, 1);
+        map.write(FAR, 7, 2);
+        assert_eq!((map.x(), map.y()), (-FAR..=FAR, 7..=7));
+        assert_eq!(map.read(0, 7), Some(0));
+        assert_eq!(map.count(|&&n| n == 0), 2 * FAR as usize - 1);
+
+        // Dense Maps may have room to spare beyond x() and y(), which isn't counted
+        let mut dense: Map<u8> = Map::new();
+        let mut sparse: Map<u8> = Map::sparse();
+        for (x, y) in [(2, 3), (-4, 0)] {
+            dense.write(x, y, 1);
+            sparse.write(x, y, 1);
+        }
+        assert_eq!(dense.count(|&&n| n == 0), 26);
+        assert_eq!(sparse.count(|&&n| n == 0), 26);
+
+        // find agrees with count on both, except sparse find only looks at written cells
+        for map in [&dense, &sparse] {
+            assert_eq!(map.find(|n| n == 1), vec![(-4, 0), (2, 3)]);
+            assert_eq!(map.count(|&&n| n == 1), 2);
+        }
+        assert_eq!(dense.find(|n| n == 0).len(), 26);
+        assert!(sparse.find(|n| n == 0).is_empty());
+    }
+
+    #[test]
+    fn bitmap() {
+        let mut bits = BitMap::ranged(0..=69, 0..=2);
+        bits.write(0, 0, true);
+        bits.write(63, 1, true);
+        bits.write(69, 2, true);
+        assert_eq!(bits.read(63, 1), Some(true));
+        assert_eq!(bits.read(64, 1), Some(false));
+        assert_eq!(bits.read(70, 1), None);
+        assert_eq!(bits.count(), 3);
+        assert_eq!(bits.find(), vec![(0, 0), (63, 1), (69, 2)]);
+
+        // Across the word boundary, and off the edges
+        let east = bits.shift(1, 0);
+        assert_eq!(east.find(), vec![(1, 0), (64, 1)]);
+        let back = east.shift(-1, 0);
+        assert_eq!(back.find(), vec![(0, 0), (63, 1)]);
+        let north = bits.shift(-64, -1);
+        assert_eq!(north.find(), vec![(5, 1)]);
+        assert_eq!(bits.shift(0, -3).count(), 0);
+
+        assert_eq!((&bits | &east).count(), 5);
+        assert_eq!((&bits & &back).find(), vec![(0, 0), (63, 1)]);
+        assert_eq!((&bits - &back).find(), vec![(69, 2)]);
+
+        let mut small = BitMap::ranged(0..=2, 0..=1);
+        small.write(1, 0, true);
+        small.write(-1, 2, true);
+        assert_eq!((small.x(), small.y()), (-1..=2, 0..=2));
+        assert_eq!(small.to_string(), "..#.\n....\n#...\n");
+        assert_eq!(small.union(&bits).find(), vec![(0, 0), (1, 0), (-1, 2)]);
+
+        // Growing a cell at a time, so it has room to spare, is the same as making room first
+        let mut trail = BitMap::ranged(0..=0, 0..=0);
+        let mut exact = BitMap::ranged(-199..=0, 0..=66);
+        for n in 0..200 {
+            trail.write(-n, n / 3, true);
+            exact.write(-n, n / 3, true);
+        }
+        assert_eq!((trail.x(), trail.y()), (-199..=0, 0..=66));
+        assert_eq!(trail, exact);
+        assert_eq!(trail.count(), 200);
+        assert_eq!(trail.read(-100, 33), Some(true));
+        assert_eq!(trail.read(-100, 34), Some(false));
+        assert_eq!((&trail - &exact).count(), 0);
+        assert_eq!(trail.shift(1, 0).count(), 199);
+        trail.write(-100, 33, false);
+        assert_ne!(trail, exact);
+    }
+
+    #[test]
+    fn grid() {
+        let mut cube: Grid<u8, 3> = Grid::ranged([0..=1, 0..=1, 0..=0]);
+        cube.write([0, 0, 0], 1);
+        cube.write([1, 1, 0], 2);
+        cube.write([1, 0, 1], 3);
+        assert_eq!(cube.ranges(), [0..=1, 0..=1, 0..=1]);
+        cube.write([-20, 0, 30], 4);
+        assert_eq!(cube.ranges(), [-20..=1, 0..=1, 0..=30]);
+        assert_eq!(cube.read([1, 1, 0]), Some(2));
+        assert_eq!(cube.read([1, 0, 1]), Some(3));
+        assert_eq!(cube.read([100, 0, 0]), None);
+        assert_eq!(cube.count(|&&n| n > 0), 4);
+        assert_eq!(
+            cube.find(|n| n > 1),
+            vec![[1, 1, 0], [1, 0, 1], [-20, 0, 30]]
+        );
+
+        assert_eq!(cube.orthogonal([0, 0, 0]).count(), 6);
+        assert_eq!(cube.surrounding([0, 0, 0]).count(), 26);
+        let near: u8 = cube.surrounding([0, 0, 0]).filter_map(|(_, n)| n).sum();
+        assert_eq!(near, 5);
+        let steps = [[0, 0, 1], [1, 1, 0]];
+        let found: Vec<_> = cube.neighbours([0, 0, 0], &steps).collect();
+        assert_eq!(found, vec![([0, 0, 1], Some(0)), ([1, 1, 0], Some(2))]);
+
+        let mut small: Grid<u8, 3> = Grid::ranged([0..=0, 0..=0, 0..=0]);
+        small.write([0, 0, 0], 1);
+        small.write([1, 1, 1], 2);
+        assert_eq!(small.to_string(), "z=0\n10\n00\n\nz=1\n00\n02\n");
+
+        let mut life: Grid<u8, 4> = Grid::new();
+        life.write([0, 0, 0, 0], 1);
+        assert_eq!(
+            life.surrounding([1, 1, 1, 1])
+                .filter(|&(_, n)| n == Some(1))
+                .count(),
+            1
+        );
+        assert_eq!(life.surrounding([0, 0, 0, 0]).count(), 80);
+    }
+
+    #[test]
+    fn grid_negative() {
+        let mut flat: Grid<u8, 2> = Grid::ranged([0..=100, 0..=100]);
+        flat.write([50, 50], 1);
+        flat.write([-5, -5], 2);
+        assert_eq!(flat.ranges(), [-5..=50, -5..=50]);
+        assert_eq!(flat.read([50, 50]), Some(1));
+        assert_eq!(flat.read([-5, -5]), Some(2));
+        assert_eq!(flat.count(|&&n| n == 0), 56 * 56 - 2);
+        assert_eq!(flat.find(|n| n > 0), vec![[-5, -5], [50, 50]]);
+    }
+
+    #[test]
+    fn distances() {
+        let maze: Map<Maze> = MAZE.parse().unwrap();
+        let spaces = maze.find_points(|m| m == Maze::Space);
+        let field = maze.distances(&spaces[..1], |m| m == Maze::Space);
+        assert_eq!((field.x(), field.y()), (maze.x(), maze.y()));
+        assert_eq!(field.read_at(spaces[0]), Some(Some(0)));
+        assert_eq!(field.count(|&&d| d.is_some()), spaces.len());
+        assert_eq!(field.read(0, 0), Some(None));
+
+        // Each step along a single row of open cells, from both ends
+        let row: Map<Digit> = "1111".parse().unwrap();
+        let ends = [Point::new(0, 0), Point::new(3, 0)];
+        let field = row.distances(&ends, |d| d.0 == 1);
+        let steps: Vec<_> = field.cells().map(|(_, d)| d).collect();
+        assert_eq!(steps, [Some(0), Some(1), Some(1), Some(0)]);
+
+        // Room beyond x() isn't part of the Map, so can't be a source
+        let mut roomy: Map<Digit> = Map::new();
+        roomy.write(0, 0, Digit(1));
+        roomy.write(1, 0, Digit(1));
+        let field = roomy.distances(&[Point::new(5, 0), Point::new(1, 0)], |d| d.0 == 1);
+        assert_eq!((field.x(), field.y()), (0..=1, 0..=0));
+        assert_eq!(field.read(0, 0), Some(Some(1)));
+    }
+
+    #[test]
+    fn equality() {
+        let maze: Map<Maze> = MAZE.parse().unwrap();
+        let mut roomy: Map<Maze> = Map::rect((-50, -50), (50, 50));
+        let mut sparse: Map<Maze> = Map::sparse();
+        for (p, cell) in maze.cells() {
+            roomy.write_at(p, cell);
+            sparse.write_at(p, cell);
+        }
+        assert!(maze == roomy && roomy == sparse);
+
+        let small: Map<Digit> = "12\n34\n".parse().unwrap();
+        let mut big: Map<Digit> = Map::new();
+        for (p, d) in small.cells() {
+            big.write_at(p, d);
+        }
+        let hasher = RandomState::new();
+        assert_eq!(hasher.hash_one(&small), hasher.hash_one(&big));
+
+        big.write(1, 1, Digit(5));
+        assert_ne!(small, big);
+        big.write(2, 0, Digit(6));
+        assert_eq!(
+            small.diff(&big),
+            vec![
+                (Point::new(2, 0), None, Some(Digit(6))),
+                (Point::new(1, 1), Some(Digit(4)), Some(Digit(5))),
+                (Point::new(2, 1), None, Some(Digit(0))),
+            ]
+        );
+        assert_eq!(small.compare(&big), "12  | 126 |   *\n34  | 350 |  **\n");
+    }
 }
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How well a Memo has been working
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Values which were already known
+    pub hits: usize,
+    /// Values which had to be calculated
+    pub misses: usize,
+    /// Values forgotten to stay within the size limit
+    pub evictions: usize,
+}
+
+/// Remembers the value of a function for each key, typically the function's arguments, so that
+/// recursive puzzle functions needn't calculate the same thing twice
+/// The function is written to take the Memo, passing it along to get_or for each recursive call
+#[derive(Clone, Debug)]
+pub struct Memo<K, V> {
+    table: HashMap<K, V>,
+    limit: Option<usize>,
+    stats: Stats,
+}
+
+impl<K: Eq + Hash, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Memo<K, V> {
+    /// A Memo which remembers everything
+    pub fn new() -> Self {
+        Self {
+            table: HashMap::new(),
+            limit: None,
+            stats: Stats::default(),
+        }
+    }
+
+    /// A Memo which never holds more than limit values, when it's full everything is forgotten
+    /// to make room, which is crude but suits puzzles where recent values are most useful
+    pub fn bounded(limit: usize) -> Self {
+        assert!(
+            limit > 0,
+            "A Memo which can't remember anything is pointless"
+        );
+        Self {
+            table: HashMap::with_capacity(limit),
+            limit: Some(limit),
+            stats: Stats::default(),
+        }
+    }
+
+    /// The value for key if known, otherwise calculate it with this Memo, then remember it
+    pub fn get_or<F>(&mut self, key: K, calculate: F) -> V
+    where
+        F: FnOnce(&mut Self) -> V,
+    {
+        if let Some(value) = self.table.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+        self.stats.misses += 1;
+        let value = calculate(self);
+        if self.limit.is_some_and(|limit| self.table.len() >= limit) {
+            self.stats.evictions += self.table.len();
+            self.table.clear();
+        }
+        self.table.insert(key, value.clone());
+        value
+    }
+
+    /// How many values are remembered now
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Forget all the values, but not the stats
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci(memo: &mut Memo<u32, u64>, n: u32) -> u64 {
+        if n < 2 {
+            return n as u64;
+        }
+        memo.get_or(n, |memo| fibonacci(memo, n - 1) + fibonacci(memo, n - 2))
+    }
+
+    #[test]
+    fn recursion() {
+        let mut memo = Memo::new();
+        assert_eq!(fibonacci(&mut memo, 90), 2_880_067_194_370_816_120);
+        // Each of 2..=90 is calculated once, and all but the two smallest are then reused
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 87,
+                misses: 89,
+                evictions: 0
+            }
+        );
+        assert_eq!(memo.len(), 89);
+        assert_eq!(fibonacci(&mut memo, 90), 2_880_067_194_370_816_120);
+        assert_eq!(memo.stats().hits, 88);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut memo = Memo::bounded(3);
+        for n in 1..=5 {
+            assert_eq!(memo.get_or(n, |_| n * 2), n * 2);
+        }
+        // Full after 3, so those were forgotten to make room for 4, then 5 joined it
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.get_or(5, |_| unreachable!("5 is remembered")), 10);
+        assert_eq!(memo.get_or(1, |_| 2), 2);
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 1,
+                misses: 6,
+                evictions: 3
+            }
+        );
+        memo.clear();
+        assert!(memo.is_empty());
+    }
+}